@@ -38,23 +38,24 @@ if (empty($data)) {
     errorResponse('缺少数据');
 }
 
-// 数据库实例
-$db = Database::getInstance();
-
-// 检查并自动创建媒体播放表（如果不存在）
-ensureMediaPlaybackTable($db);
+// 批量推送的 samples 中每一项都是一条完整的采集数据；旧版客户端没有 type 字段，按单条处理
+$samples = [$data];
+if (($data['type'] ?? 'single') === 'batch') {
+    if (!isset($data['samples']) || !is_array($data['samples'])) {
+        errorResponse('批量数据缺少 samples');
+    }
+    $samples = $data['samples'];
+}
 
-try {
-    $db->beginTransaction();
-    
-    $deviceId = $device['id'];
-    $timestamp = date('Y-m-d H:i:s');
-    
-    // 更新设备最后在线时间
-    $db->execute(
-        'UPDATE devices SET last_seen_at = ?, is_online = 1 WHERE id = ?',
-        [$timestamp, $deviceId]
-    );
+/**
+ * 保存一条采集数据，返回记录使用的时间
+ */
+function storeSample($db, $deviceId, $data, $receivedAt) {
+    // 使用客户端的采集时间（Unix 秒），离线补发的数据按原时间记录；不接受未来的时间
+    $timestamp = $receivedAt;
+    if (isset($data['timestamp']) && is_numeric($data['timestamp']) && (int)$data['timestamp'] > 0) {
+        $timestamp = date('Y-m-d H:i:s', min((int)$data['timestamp'], time()));
+    }
     
     // 如果有computer_name，更新设备信息
     if (isset($data['computer_name']) && !empty($data['computer_name'])) {
@@ -159,6 +160,19 @@ try {
     // 插入媒体播放信息（可选，兼容旧版本）
     if (isset($data['media']) && is_array($data['media']) && !empty($data['media']['title'])) {
         try {
+            $thumbnail = $data['media']['thumbnail'] ?? null;
+            // 缩略图哈希与上一次推送成功时相同时客户端省略 thumbnail，沿用之前保存的缩略图
+            if (!array_key_exists('thumbnail', $data['media']) && !empty($data['media']['thumbnail_hash'])) {
+                $previous = $db->fetchOne(
+                    'SELECT thumbnail FROM media_playback 
+                     WHERE device_id = ? AND thumbnail IS NOT NULL 
+                     ORDER BY timestamp DESC, id DESC 
+                     LIMIT 1',
+                    [$deviceId]
+                );
+                $thumbnail = $previous['thumbnail'] ?? null;
+            }
+            
             $db->execute(
                 'INSERT INTO media_playback (device_id, title, artist, album, duration, position, playback_status, media_type, thumbnail, timestamp) 
                  VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)',
@@ -171,7 +185,7 @@ try {
                     $data['media']['position'] ?? null,
                     $data['media']['playback_status'] ?? 'Playing',
                     $data['media']['media_type'] ?? 'Music',
-                    $data['media']['thumbnail'] ?? null,
+                    $thumbnail,
                     $timestamp
                 ]
             );
@@ -181,6 +195,90 @@ try {
         }
     }
     
+    return $timestamp;
+}
+
+/**
+ * 把增量数据合并到上次保存的完整数据上
+ * 带 seq 的数据来自增量推送：mode 为 full 时是完整快照，为 delta 时只包含变化的分项。
+ * 返回 [合并后的数据（没有可合并的状态时为 null）, 新的状态, 是否需要客户端重新同步]
+ */
+function applyPushDelta($sample, $pushState) {
+    $seq = (int)$sample['seq'];
+    $resync = false;
+
+    if (($sample['mode'] ?? 'full') === 'delta') {
+        if ($pushState === null) {
+            // 没有完整快照可以合并（例如服务器清空了状态），只能等客户端重新发送
+            return [null, null, true];
+        }
+        // 序号不连续说明中间有数据丢失，仍按已知状态合并，同时要求客户端发送完整快照
+        $resync = $seq !== $pushState['seq'] + 1;
+        $sample = array_merge($pushState['data'], $sample);
+    }
+
+    $data = array_diff_key($sample, array_flip(['version', 'type', 'timestamp', 'seq', 'mode']));
+    return [$sample, ['seq' => $seq, 'data' => $data], $resync];
+}
+
+// 数据库实例
+$db = Database::getInstance();
+
+// 检查并自动创建媒体播放表（如果不存在）
+ensureMediaPlaybackTable($db);
+ensureDevicePushStateTable($db);
+
+try {
+    $db->beginTransaction();
+    
+    $deviceId = $device['id'];
+    $receivedAt = date('Y-m-d H:i:s');
+    
+    // 更新设备最后在线时间
+    $db->execute(
+        'UPDATE devices SET last_seen_at = ?, is_online = 1 WHERE id = ?',
+        [$receivedAt, $deviceId]
+    );
+    
+    // 增量推送的状态
+    $pushState = null;
+    $row = $db->fetchOne('SELECT last_seq, state FROM device_push_state WHERE device_id = ?', [$deviceId]);
+    if ($row) {
+        $pushState = ['seq' => (int)$row['last_seq'], 'data' => json_decode($row['state'], true) ?: []];
+    }
+    $pushStateChanged = false;
+    $resync = false;
+    
+    $timestamp = $receivedAt;
+    $stored = 0;
+    foreach ($samples as $sample) {
+        if (!is_array($sample)) {
+            continue;
+        }
+        
+        if (isset($sample['seq']) && is_numeric($sample['seq'])) {
+            [$sample, $pushState, $needResync] = applyPushDelta($sample, $pushState);
+            $pushStateChanged = true;
+            $resync = $resync || $needResync;
+            if ($sample === null) {
+                continue;
+            }
+        }
+        
+        $timestamp = storeSample($db, $deviceId, $sample, $receivedAt);
+        $stored++;
+    }
+    
+    if ($pushStateChanged) {
+        $db->execute('DELETE FROM device_push_state WHERE device_id = ?', [$deviceId]);
+        if ($pushState !== null) {
+            $db->execute(
+                'INSERT INTO device_push_state (device_id, last_seq, state, updated_at) VALUES (?, ?, ?, ?)',
+                [$deviceId, $pushState['seq'], json_encode($pushState['data'], JSON_UNESCAPED_UNICODE), $receivedAt]
+            );
+        }
+    }
+    
     $db->commit();
     
     // 自动清理旧数据（基于时间间隔，不会每次都执行）
@@ -189,7 +287,9 @@ try {
     // 准备响应数据
     $responseData = [
         'device_id' => $deviceId,
-        'timestamp' => $timestamp
+        'timestamp' => $timestamp,
+        'stored' => $stored,
+        'resync' => $resync
     ];
     
     // 如果执行了清理，添加清理信息到响应（可选，用于调试）
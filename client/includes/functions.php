@@ -185,6 +185,21 @@ function getPostData() {
     
     if (strpos($contentType, 'application/json') !== false) {
         $json = file_get_contents('php://input');
+        
+        // 客户端可以压缩请求体；不支持的压缩格式返回 415，客户端会改为发送未压缩的数据
+        $encoding = strtolower(trim($_SERVER['HTTP_CONTENT_ENCODING'] ?? ''));
+        if ($encoding === 'gzip') {
+            $json = gzdecode($json);
+        } elseif ($encoding === 'zstd' && function_exists('zstd_uncompress')) {
+            $json = zstd_uncompress($json);
+        } elseif ($encoding !== '' && $encoding !== 'identity') {
+            errorResponse('不支持的压缩格式: ' . $encoding, 415);
+        }
+        
+        if ($json === false) {
+            errorResponse('解压请求数据失败');
+        }
+        
         return json_decode($json, true);
     }
     
@@ -582,3 +597,37 @@ function ensureMediaPlaybackTable($db) {
     }
 }
 
+
+/**
+ * 检查并创建增量推送状态表
+ * 每台设备保存最后收到的序号和合并后的完整数据，用于把增量数据还原为完整数据
+ * 
+ * @param Database $db 数据库实例
+ * @return bool 表是否存在或成功创建
+ */
+function ensureDevicePushStateTable($db) {
+    try {
+        if (DB_TYPE === 'mysql') {
+            $db->execute("CREATE TABLE IF NOT EXISTS device_push_state (
+                device_id INT PRIMARY KEY,
+                last_seq BIGINT UNSIGNED NOT NULL DEFAULT 0 COMMENT '最后收到的推送序号',
+                state MEDIUMTEXT NOT NULL COMMENT '合并后的完整数据（JSON）',
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (device_id) REFERENCES devices(id) ON DELETE CASCADE
+            ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COMMENT='增量推送状态'");
+        } else {
+            // SQLite
+            $db->execute("CREATE TABLE IF NOT EXISTS device_push_state (
+                device_id INTEGER PRIMARY KEY,
+                last_seq INTEGER NOT NULL DEFAULT 0,
+                state TEXT NOT NULL,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (device_id) REFERENCES devices(id) ON DELETE CASCADE
+            )");
+        }
+        return true;
+    } catch (Exception $e) {
+        error_log("创建增量推送状态表失败: " . $e->getMessage());
+        return false;
+    }
+}
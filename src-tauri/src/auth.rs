@@ -0,0 +1,439 @@
+// 本地 HTTP API 的访问令牌
+//
+// 请求通过 `Authorization: Bearer <token>` 携带令牌，每个令牌带有名称、可选的过期时间
+// 和可见的数据范围。浏览器的 EventSource / WebSocket 无法设置请求头，因此也接受
+// `?access_token=` 查询参数。令牌保存在 AppState 中，增删后立即生效，无需重启服务器。
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::SystemTime;
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::{AppState, SettingsSection};
+use crate::system_info::SystemInfo;
+
+/// SystemInfo 中可单独授权的数据范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiSection {
+    ComputerName,
+    Uptime,
+    Cpu,
+    Memory,
+    Processes,       // 完整进程列表（包含聚焦进程）
+    FocusedProcess,  // 仅当前聚焦的进程
+    Disks,
+    Network,
+    Battery,
+    Media,
+}
+
+impl ApiSection {
+    pub const ALL: [ApiSection; 10] = [
+        ApiSection::ComputerName,
+        ApiSection::Uptime,
+        ApiSection::Cpu,
+        ApiSection::Memory,
+        ApiSection::Processes,
+        ApiSection::FocusedProcess,
+        ApiSection::Disks,
+        ApiSection::Network,
+        ApiSection::Battery,
+        ApiSection::Media,
+    ];
+}
+
+/// 访问令牌
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessToken {
+    pub name: String,
+    pub token: String,
+    pub expires_at: Option<u64>,     // 过期时间（Unix 秒），None 表示永不过期
+    pub sections: Vec<ApiSection>,   // 可见的数据范围
+}
+
+impl AccessToken {
+    fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+}
+
+/// 生成随机令牌字符串
+pub fn generate_token() -> String {
+    let bytes: [u8; 24] = rand::thread_rng().gen();
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 创建令牌，名称重复时返回错误
+pub fn create_access_token(
+    state: &AppState,
+    name: String,
+    expires_at: Option<u64>,
+    sections: Vec<ApiSection>,
+) -> Result<AccessToken, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("令牌名称不能为空".to_string());
+    }
+
+    let access_token = AccessToken {
+        name,
+        token: generate_token(),
+        expires_at,
+        sections,
+    };
+
+    {
+        let mut tokens = state.access_tokens.lock().unwrap();
+        if tokens.iter().any(|t| t.name == access_token.name) {
+            return Err(format!("令牌 {} 已存在", access_token.name));
+        }
+        tokens.push(access_token.clone());
+    }
+
+    state.save_settings(SettingsSection::AccessTokens)?;
+    Ok(access_token)
+}
+
+/// 吊销令牌
+pub fn revoke_access_token(state: &AppState, name: &str) -> Result<(), String> {
+    {
+        let mut tokens = state.access_tokens.lock().unwrap();
+        let before = tokens.len();
+        tokens.retain(|t| t.name != name);
+        if tokens.len() == before {
+            return Err(format!("令牌 {} 不存在", name));
+        }
+    }
+
+    state.save_settings(SettingsSection::AccessTokens)
+}
+
+// 固定时间比较，避免通过响应时间猜测令牌
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// 当前请求被授予的访问范围
+#[derive(Debug, Clone)]
+pub struct ApiAccess {
+    pub token_name: Option<String>,
+    pub sections: Vec<ApiSection>,
+    token: Option<String>,
+}
+
+impl ApiAccess {
+    /// 未携带令牌且服务器不要求认证时的访问范围
+    pub fn anonymous() -> Self {
+        Self {
+            token_name: None,
+            sections: ApiSection::ALL.to_vec(),
+            token: None,
+        }
+    }
+
+    /// 长连接在每次推送前重新检查授权，令牌被吊销或过期后断开
+    pub fn is_still_valid(&self, state: &AppState) -> bool {
+        match &self.token {
+            None => !state.http_settings.lock().unwrap().require_auth,
+            Some(token) => state.access_tokens.lock().unwrap()
+                .iter()
+                .any(|t| t.token == *token && !t.is_expired(unix_now())),
+        }
+    }
+
+    pub fn allows(&self, section: ApiSection) -> bool {
+        self.sections.contains(&section)
+    }
+
+    /// 去掉令牌无权查看的数据
+    pub fn restrict(&self, mut info: SystemInfo) -> SystemInfo {
+        if !self.allows(ApiSection::ComputerName) {
+            info.computer_name = None;
+        }
+        if !self.allows(ApiSection::Uptime) {
+            info.uptime = None;
+        }
+        if !self.allows(ApiSection::Cpu) {
+            info.cpu_usage = None;
+        }
+        if !self.allows(ApiSection::Memory) {
+            info.memory_usage = None;
+        }
+        if !self.allows(ApiSection::Processes) {
+            info.processes = if self.allows(ApiSection::FocusedProcess) {
+                info.processes.map(|processes| processes.into_iter().filter(|p| p.is_focused).collect())
+            } else {
+                None
+            };
+        }
+        if !self.allows(ApiSection::Disks) {
+            info.disks = None;
+        }
+        if !self.allows(ApiSection::Network) {
+            info.network = None;
+        }
+        if !self.allows(ApiSection::Battery) {
+            info.battery = None;
+        }
+        if !self.allows(ApiSection::Media) {
+            info.media = None;
+            info.media_sessions = None;
+        }
+        info
+    }
+}
+
+fn unauthorized(message: &'static str) -> Response {
+    (
+        StatusCode::UNAUTHORIZED,
+        [(header::WWW_AUTHENTICATE, "Bearer")],
+        message,
+    ).into_response()
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for ApiAccess {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let bearer = parts.headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|token| token.trim().to_string())
+            .or_else(|| {
+                Query::<HashMap<String, String>>::try_from_uri(&parts.uri)
+                    .ok()
+                    .and_then(|Query(mut query)| query.remove("access_token"))
+            });
+
+        let Some(bearer) = bearer else {
+            return if state.http_settings.lock().unwrap().require_auth {
+                Err(unauthorized("缺少访问令牌"))
+            } else {
+                Ok(ApiAccess::anonymous())
+            };
+        };
+
+        let now = unix_now();
+
+        // 携带了令牌就必须有效，即使服务器不要求认证
+        let tokens = state.access_tokens.lock().unwrap();
+        match tokens.iter().find(|t| constant_time_eq(t.token.as_bytes(), bearer.as_bytes())) {
+            Some(token) if !token.is_expired(now) => Ok(ApiAccess {
+                token_name: Some(token.name.clone()),
+                sections: token.sections.clone(),
+                token: Some(token.token.clone()),
+            }),
+            Some(_) => Err(unauthorized("访问令牌已过期")),
+            None => Err(unauthorized("无效的访问令牌")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+    use crate::battery::BatteryInfo;
+    use crate::config::Config;
+    use crate::system_info::{DiskInfo, MemoryInfo, NetworkInfo, ProcessInfo};
+
+    fn token(name: &str, expires_at: Option<u64>, sections: Vec<ApiSection>) -> AccessToken {
+        AccessToken {
+            name: name.to_string(),
+            token: generate_token(),
+            expires_at,
+            sections,
+        }
+    }
+
+    // 直接写入 AppState，不经过 create_access_token，避免测试改动本机配置文件
+    fn state_with(require_auth: bool, tokens: Vec<AccessToken>) -> Arc<AppState> {
+        let mut config = Config::default();
+        config.http.require_auth = require_auth;
+        config.access_tokens = tokens;
+        Arc::new(AppState::new(&config))
+    }
+
+    async fn extract(state: &Arc<AppState>, uri: &str, bearer: Option<&str>) -> Result<ApiAccess, StatusCode> {
+        let mut request = Request::builder().uri(uri);
+        if let Some(bearer) = bearer {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", bearer));
+        }
+        let (mut parts, _) = request.body(()).unwrap().into_parts();
+        ApiAccess::from_request_parts(&mut parts, state)
+            .await
+            .map_err(|response| response.status())
+    }
+
+    fn process(pid: u32, is_focused: bool) -> ProcessInfo {
+        ProcessInfo {
+            memory: 100,
+            is_focused,
+            window_title: String::new(),
+            executable_name: format!("app{}", pid),
+            pid,
+            cpu_usage: 1.0,
+        }
+    }
+
+    fn full_info() -> SystemInfo {
+        let media: crate::media_monitor::MediaInfo = serde_json::from_value(serde_json::json!({
+            "title": "Song",
+            "playback_status": "Playing",
+            "media_type": "Music",
+        }))
+        .unwrap();
+
+        SystemInfo {
+            computer_name: Some("pc".to_string()),
+            uptime: Some(60),
+            cpu_usage: Some(vec![10.0]),
+            memory_usage: Some(MemoryInfo { total: 100, used: 50, percent: 50.0 }),
+            processes: Some(vec![process(1, false), process(2, true), process(3, false)]),
+            disks: Some(vec![DiskInfo {
+                name: "sda".to_string(),
+                mount_point: "/".to_string(),
+                total_space: 100,
+                available_space: 50,
+            }]),
+            network: Some(vec![NetworkInfo { name: "eth0".to_string(), received: 1, transmitted: 2 }]),
+            battery: Some(BatteryInfo { percentage: 80.0, is_charging: false, status: "放电中".to_string() }),
+            media: Some(media.clone()),
+            media_sessions: Some(vec![media]),
+        }
+    }
+
+    #[tokio::test]
+    async fn expired_token_is_rejected() {
+        let expired = token("old", Some(unix_now() - 1), ApiSection::ALL.to_vec());
+        let valid = token("new", Some(unix_now() + 3600), ApiSection::ALL.to_vec());
+        let state = state_with(false, vec![expired.clone(), valid.clone()]);
+
+        assert_eq!(extract(&state, "/api/system", Some(&expired.token)).await.unwrap_err(), StatusCode::UNAUTHORIZED);
+        let access = extract(&state, "/api/system", Some(&valid.token)).await.unwrap();
+        assert_eq!(access.token_name.as_deref(), Some("new"));
+    }
+
+    #[tokio::test]
+    async fn invalid_token_is_rejected_even_without_require_auth() {
+        let state = state_with(false, vec![token("a", None, ApiSection::ALL.to_vec())]);
+
+        assert_eq!(extract(&state, "/api/system", Some("not-a-token")).await.unwrap_err(), StatusCode::UNAUTHORIZED);
+        assert_eq!(extract(&state, "/api/system?access_token=", None).await.unwrap_err(), StatusCode::UNAUTHORIZED);
+        // 不带令牌时仍可匿名访问
+        let access = extract(&state, "/api/system", None).await.unwrap();
+        assert!(access.token_name.is_none());
+        assert_eq!(access.sections, ApiSection::ALL.to_vec());
+    }
+
+    #[tokio::test]
+    async fn anonymous_request_is_rejected_when_auth_required() {
+        let state = state_with(true, vec![token("a", None, ApiSection::ALL.to_vec())]);
+
+        assert_eq!(extract(&state, "/api/system", None).await.unwrap_err(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn query_token_is_accepted_like_bearer_header() {
+        let scoped = token("scoped", None, vec![ApiSection::Cpu, ApiSection::Memory]);
+        let state = state_with(true, vec![scoped.clone()]);
+
+        let from_header = extract(&state, "/api/system", Some(&scoped.token)).await.unwrap();
+        let from_query = extract(&state, &format!("/api/stream?mode=full&access_token={}", scoped.token), None)
+            .await
+            .unwrap();
+        assert_eq!(from_header.token_name, from_query.token_name);
+        assert_eq!(from_header.sections, from_query.sections);
+        assert_eq!(from_query.sections, vec![ApiSection::Cpu, ApiSection::Memory]);
+
+        assert_eq!(
+            extract(&state, "/api/stream?access_token=wrong", None).await.unwrap_err(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[tokio::test]
+    async fn revoked_token_is_no_longer_valid() {
+        let kept = token("kept", None, ApiSection::ALL.to_vec());
+        let revoked = token("revoked", None, ApiSection::ALL.to_vec());
+        let state = state_with(true, vec![kept.clone(), revoked.clone()]);
+
+        let access = extract(&state, "/api/system", Some(&revoked.token)).await.unwrap();
+        let other = extract(&state, "/api/system", Some(&kept.token)).await.unwrap();
+        assert!(access.is_still_valid(&state));
+
+        // 与 revoke_access_token 相同的移除方式（不写配置文件）
+        state.access_tokens.lock().unwrap().retain(|t| t.name != "revoked");
+        assert!(!access.is_still_valid(&state));
+        assert!(other.is_still_valid(&state));
+
+        // 匿名连接在服务器改为要求认证后同样失效
+        let open = state_with(false, Vec::new());
+        let anonymous = ApiAccess::anonymous();
+        assert!(anonymous.is_still_valid(&open));
+        open.http_settings.lock().unwrap().require_auth = true;
+        assert!(!anonymous.is_still_valid(&open));
+    }
+
+    #[test]
+    fn restrict_removes_sections_not_granted() {
+        let access = |sections: Vec<ApiSection>| ApiAccess { token_name: Some("t".to_string()), sections, token: None };
+
+        let info = access(vec![ApiSection::Cpu]).restrict(full_info());
+        assert!(info.cpu_usage.is_some());
+        assert!(info.computer_name.is_none());
+        assert!(info.uptime.is_none());
+        assert!(info.memory_usage.is_none());
+        assert!(info.processes.is_none());
+        assert!(info.disks.is_none());
+        assert!(info.network.is_none());
+        assert!(info.battery.is_none());
+        assert!(info.media.is_none());
+        assert!(info.media_sessions.is_none());
+
+        let info = access(ApiSection::ALL.to_vec()).restrict(full_info());
+        assert!(info.computer_name.is_some() && info.uptime.is_some() && info.memory_usage.is_some());
+        assert!(info.disks.is_some() && info.network.is_some() && info.battery.is_some());
+        assert!(info.media.is_some() && info.media_sessions.is_some());
+        assert_eq!(info.processes.unwrap().len(), 3);
+    }
+
+    #[test]
+    fn focused_process_scope_keeps_only_focused_process() {
+        let access = ApiAccess {
+            token_name: Some("t".to_string()),
+            sections: vec![ApiSection::FocusedProcess, ApiSection::Media],
+            token: None,
+        };
+
+        let info = access.restrict(full_info());
+        let processes = info.processes.unwrap();
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].pid, 2);
+        assert!(processes[0].is_focused);
+        assert!(info.media.is_some());
+        assert!(info.cpu_usage.is_none());
+
+        // 同时授予 Processes 时保留完整列表
+        let access = ApiAccess {
+            sections: vec![ApiSection::Processes, ApiSection::FocusedProcess],
+            ..access
+        };
+        assert_eq!(access.restrict(full_info()).processes.unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,273 @@
+// 无界面的采集代理：不依赖 Tauri、托盘或 webview，适用于服务器和容器
+//
+// 与桌面应用读取同一个配置文件。不带子命令运行时按配置启动本地 HTTP API 和远程推送，
+// 子命令用于脚本和定时任务。
+use std::process::ExitCode;
+use clap::{Parser, Subcommand};
+use watchmedo::{config, http_server, remote_push, AppState};
+use watchmedo::config::{Config, PushTarget};
+use watchmedo::system_info::build_system_info;
+
+/// 推送失败（网络错误或服务器返回非 2xx）
+const EXIT_PUSH_FAILED: u8 = 1;
+/// 配置或参数错误
+const EXIT_CONFIG_ERROR: u8 = 2;
+
+#[derive(Parser)]
+#[command(name = "watchmedo-agent", version, about = "WatchMeDo 无界面采集代理")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 按配置启动 HTTP API 和远程推送（默认）
+    Run,
+    /// 输出一次系统信息 JSON（遵循共享设置）
+    Snapshot {
+        /// 格式化输出
+        #[arg(long)]
+        pretty: bool,
+    },
+    /// 向远程服务器推送
+    Push {
+        /// 只推送一次后退出，成功返回 0，推送失败返回 1，配置错误返回 2
+        #[arg(long)]
+        once: bool,
+        /// 推送目标的 id，省略时使用所有已开启且配置了 URL 的目标
+        #[arg(long)]
+        target: Option<String>,
+    },
+    /// 读取或修改配置
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// 只启动本地 HTTP API
+    Serve,
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// 读取配置项（例如 remote_targets.0.url），省略时输出完整配置
+    Get { key: Option<String> },
+    /// 修改配置项，例如 `config set share.share_processes false`
+    Set { key: String, value: String },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match cli.command.unwrap_or(Command::Run) {
+        Command::Run => run().await,
+        Command::Snapshot { pretty } => snapshot(pretty).await,
+        Command::Push { once, target } => push(once, target).await,
+        Command::Config { action } => config_command(action),
+        Command::Serve => serve().await,
+    }
+}
+
+// 启动采集任务并返回共享状态
+fn start_state(config: &Config) -> AppState {
+    let state = AppState::new(config);
+    state.collector.start();
+    state
+}
+
+// systemd 和 docker stop 发送 SIGTERM
+#[cfg(unix)]
+async fn terminate_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::terminate()) {
+        Ok(mut terminate) => {
+            terminate.recv().await;
+        }
+        Err(e) => {
+            eprintln!("监听 SIGTERM 失败: {}", e);
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn terminate_signal() {
+    std::future::pending::<()>().await;
+}
+
+async fn wait_for_exit(state: &AppState) {
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            if let Err(e) = result {
+                eprintln!("等待退出信号失败: {}", e);
+            }
+        }
+        _ = terminate_signal() => {}
+    }
+    println!("正在退出...");
+    state.finish_media_history();
+}
+
+async fn run() -> ExitCode {
+    let state = start_state(&config::load_config());
+    let app_settings = state.app_settings.lock().unwrap().clone();
+
+    if app_settings.auto_start_http {
+        start_http(&state).await;
+    }
+
+    remote_push::start_enabled_targets(&state).await;
+
+    wait_for_exit(&state).await;
+    ExitCode::SUCCESS
+}
+
+async fn start_http(state: &AppState) -> bool {
+    let http_settings = state.http_settings.lock().unwrap().clone();
+    match http_server::start_http_server(state.clone()).await {
+        Ok(()) => {
+            println!("HTTP服务器已启动: {} 端口 {}", http_settings.bind_addresses.join(", "), http_settings.port);
+            true
+        }
+        Err(e) => {
+            eprintln!("启动HTTP服务器失败: {}", e);
+            false
+        }
+    }
+}
+
+async fn snapshot(pretty: bool) -> ExitCode {
+    let state = start_state(&config::load_config());
+    state.collector.ready().await;
+
+    let share_settings = state.share_settings.lock().unwrap().clone();
+    let process_limit = state.app_settings.lock().unwrap().process_limit;
+    let info = build_system_info(&state.collector.snapshot(), &share_settings, process_limit);
+
+    let json = if pretty {
+        serde_json::to_string_pretty(&info)
+    } else {
+        serde_json::to_string(&info)
+    };
+
+    match json {
+        Ok(json) => {
+            println!("{}", json);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("序列化系统信息失败: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn push(once: bool, target: Option<String>) -> ExitCode {
+    let config = config::load_config();
+    let targets: Vec<PushTarget> = config.remote_targets
+        .iter()
+        .filter(|t| !t.remote.url.is_empty())
+        .filter(|t| match &target {
+            Some(id) => &t.id == id,
+            None => t.remote.enabled,
+        })
+        .cloned()
+        .collect();
+
+    if targets.is_empty() {
+        match &target {
+            Some(id) => eprintln!("推送目标 {} 不存在或未配置URL", id),
+            None => eprintln!(
+                "没有已开启的推送目标，请先执行: watchmedo-agent config set remote_targets '[{{\"id\": \"default\", \"url\": \"<URL>\", \"enabled\": true}}]'，或用 --target 指定目标"
+            ),
+        }
+        return ExitCode::from(EXIT_CONFIG_ERROR);
+    }
+
+    let state = start_state(&config);
+
+    if once {
+        state.collector.ready().await;
+        let mut failed = false;
+        for target in &targets {
+            match remote_push::push_once(&state, &target.id).await {
+                Ok(()) => println!("推送成功: {}", target.name),
+                Err(e) => {
+                    eprintln!("{}: {}", target.name, e);
+                    failed = true;
+                }
+            }
+        }
+        return if failed {
+            ExitCode::from(EXIT_PUSH_FAILED)
+        } else {
+            ExitCode::SUCCESS
+        };
+    }
+
+    // 只在本次运行中推送，不修改配置中的 enabled
+    for target in &targets {
+        if let Err(e) = remote_push::spawn_remote_push(&state, &target.id) {
+            eprintln!("启动远程推送失败 {}: {}", target.name, e);
+            return ExitCode::from(EXIT_CONFIG_ERROR);
+        }
+        println!("远程推送服务已启动: {} ({})", target.name, target.remote.url);
+    }
+
+    wait_for_exit(&state).await;
+    ExitCode::SUCCESS
+}
+
+async fn serve() -> ExitCode {
+    let state = start_state(&config::load_config());
+
+    if !start_http(&state).await {
+        return ExitCode::FAILURE;
+    }
+
+    wait_for_exit(&state).await;
+    ExitCode::SUCCESS
+}
+
+fn config_command(action: ConfigAction) -> ExitCode {
+    let mut config = config::load_config();
+
+    match action {
+        ConfigAction::Get { key } => {
+            let value = match key {
+                Some(key) => config::get_config_value(&config, &key),
+                None => serde_json::to_value(&config).map_err(|e| e.to_string()),
+            };
+
+            match value {
+                // 字符串直接输出原文，便于在脚本中使用
+                Ok(serde_json::Value::String(s)) => println!("{}", s),
+                Ok(value) => println!("{}", serde_json::to_string_pretty(&value).unwrap_or_default()),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return ExitCode::from(EXIT_CONFIG_ERROR);
+                }
+            }
+        }
+        ConfigAction::Set { key, value } => {
+            let previous_targets = config.remote_targets.clone();
+            if let Err(e) = config::set_config_value(&mut config, &key, &value)
+                .and_then(|_| config::save_config(&config))
+            {
+                eprintln!("{}", e);
+                return ExitCode::from(EXIT_CONFIG_ERROR);
+            }
+
+            // 被删除的推送目标的未发送数据
+            for target in previous_targets.iter().filter(|old| !config.remote_targets.iter().any(|target| target.id == old.id)) {
+                if let Err(e) = remote_push::remove_queue(&target.id) {
+                    eprintln!("[{}] {}", target.name, e);
+                }
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,255 @@
+// 后台系统信息采集模块
+//
+// 持有一个长期存在的 sysinfo::System，按配置的间隔刷新，并发布不可变快照。
+// HTTP 接口、Tauri 命令和远程推送都只读取最新快照，不再各自执行完整的进程扫描。
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use sysinfo::{System, Networks, Disks};
+use tokio::sync::watch;
+use crate::media_history::MediaHistory;
+use crate::media_monitor::{self, MediaInfo, MediaSettings};
+use crate::battery::{get_battery_info, BatteryInfo};
+use crate::system_info::{DiskInfo, ProcessInfo};
+use crate::windows_helper;
+
+/// 网络接口采样（包含两次刷新之间计算出的速率）
+#[derive(Debug, Clone)]
+pub struct NetworkSample {
+    pub name: String,
+    pub received: u64,          // 累计接收字节
+    pub transmitted: u64,       // 累计发送字节
+    pub received_rate: u64,     // 接收速率（字节/秒）
+    pub transmitted_rate: u64,  // 发送速率（字节/秒）
+}
+
+/// 一次采集得到的系统状态快照
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub taken_at: Option<SystemTime>,     // 采集时间，None 表示尚未完成首次采集
+    pub computer_name: Option<String>,
+    pub uptime: u64,
+    pub cpu_usage: Vec<f32>,              // 每个核心的占用率
+    pub memory_total: u64,
+    pub memory_used: u64,
+    pub processes: Vec<ProcessInfo>,      // 全部进程，按 CPU 占用率降序
+    pub disks: Vec<DiskInfo>,
+    pub networks: Vec<NetworkSample>,
+    pub battery: Option<BatteryInfo>,
+    pub media_sessions: Vec<MediaInfo>,   // 所有媒体会话，主会话在第一位
+}
+
+impl Snapshot {
+    /// 媒体会话，正在播放的会话播放位置推算到 now
+    pub fn media_sessions_at(&self, now: SystemTime) -> Vec<MediaInfo> {
+        let now = media_monitor::unix_millis(now);
+        self.media_sessions
+            .iter()
+            .cloned()
+            .map(|mut media| {
+                media.extrapolate_to(now);
+                media
+            })
+            .collect()
+    }
+}
+
+/// 采集器内部状态，只在阻塞线程中使用
+struct Sampler {
+    sys: System,
+    networks: Networks,
+    disks: Disks,
+    last_refresh: Instant,
+}
+
+impl Sampler {
+    fn new() -> Self {
+        let sys = System::new_all();
+        let networks = Networks::new_with_refreshed_list();
+        let disks = Disks::new_with_refreshed_list();
+
+        // sysinfo 需要两次刷新之间间隔一段时间才能计算出 CPU 占用率
+        std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+
+        Self {
+            sys,
+            networks,
+            disks,
+            last_refresh: Instant::now(),
+        }
+    }
+
+    fn sample(&mut self, media_settings: &MediaSettings) -> Snapshot {
+        self.sys.refresh_all();
+        self.networks.refresh_list();
+        self.disks.refresh_list();
+
+        let elapsed = self.last_refresh.elapsed().as_secs_f64().max(0.001);
+        self.last_refresh = Instant::now();
+
+        let computer_name = hostname::get().ok().and_then(|h| h.into_string().ok());
+
+        let cpu_usage = self.sys.cpus().iter().map(|cpu| cpu.cpu_usage()).collect();
+
+        // Get focused window PID
+        let focused_pid = windows_helper::get_focused_pid();
+
+        // Get all window titles
+        let window_titles = windows_helper::get_window_titles();
+
+        let mut processes: Vec<ProcessInfo> = self.sys.processes()
+            .iter()
+            .map(|(pid, process)| {
+                let pid_u32 = pid.as_u32();
+
+                // Use window title if available, otherwise use process name
+                let window_title = window_titles.get(&pid_u32)
+                    .cloned()
+                    .unwrap_or_else(|| process.name().to_string());
+
+                ProcessInfo {
+                    memory: process.memory(),
+                    is_focused: focused_pid == Some(pid_u32),
+                    window_title,
+                    executable_name: process.name().to_string(),
+                    pid: pid_u32,
+                    cpu_usage: process.cpu_usage(),
+                }
+            })
+            .collect();
+
+        // Sort by CPU usage descending
+        processes.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+
+        let disks = self.disks
+            .iter()
+            .map(|disk| DiskInfo {
+                name: disk.name().to_string_lossy().to_string(),
+                mount_point: disk.mount_point().to_string_lossy().to_string(),
+                total_space: disk.total_space(),
+                available_space: disk.available_space(),
+            })
+            .collect();
+
+        let networks = self.networks
+            .iter()
+            .map(|(name, network)| NetworkSample {
+                name: name.to_string(),
+                received: network.total_received(),
+                transmitted: network.total_transmitted(),
+                // received()/transmitted() 是自上次刷新以来的增量
+                received_rate: (network.received() as f64 / elapsed) as u64,
+                transmitted_rate: (network.transmitted() as f64 / elapsed) as u64,
+            })
+            .collect();
+
+        Snapshot {
+            taken_at: Some(SystemTime::now()),
+            computer_name,
+            uptime: System::uptime(),
+            cpu_usage,
+            memory_total: self.sys.total_memory(),
+            memory_used: self.sys.used_memory(),
+            processes,
+            disks,
+            networks,
+            battery: get_battery_info(),
+            media_sessions: media_monitor::get_media_sessions_sync(media_settings),
+        }
+    }
+}
+
+/// 后台采集任务的句柄，可被克隆并在各处共享
+#[derive(Clone)]
+pub struct Collector {
+    interval: Arc<Mutex<Duration>>,
+    media_settings: Arc<Mutex<MediaSettings>>,
+    media_history: Arc<Mutex<MediaHistory>>,
+    sender: Arc<watch::Sender<Arc<Snapshot>>>,
+    handle: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
+}
+
+impl Collector {
+    /// media_settings 与 AppState 共享，修改后下一轮采集生效；每次采集后用媒体会话更新 media_history
+    pub fn new(interval: Duration, media_settings: Arc<Mutex<MediaSettings>>, media_history: Arc<Mutex<MediaHistory>>) -> Self {
+        Self {
+            interval: Arc::new(Mutex::new(interval.max(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL))),
+            media_settings,
+            media_history,
+            sender: Arc::new(watch::Sender::new(Arc::new(Snapshot::default()))),
+            handle: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// 启动采集任务（需要在 tokio 运行时中调用），已有任务会被替换
+    pub fn start(&self) {
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            handle.abort();
+        }
+
+        let interval = self.interval.clone();
+        let media_settings = self.media_settings.clone();
+        let media_history = self.media_history.clone();
+        let sender = self.sender.clone();
+        let handle = tokio::spawn(async move {
+            let mut sampler = match tokio::task::spawn_blocking(Sampler::new).await {
+                Ok(sampler) => sampler,
+                Err(e) => {
+                    eprintln!("初始化采集器失败: {}", e);
+                    return;
+                }
+            };
+
+            loop {
+                let started = Instant::now();
+
+                // 刷新是阻塞操作，放到阻塞线程池执行
+                let settings = media_settings.lock().unwrap().clone();
+                let history = media_history.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    let snapshot = sampler.sample(&settings);
+                    // 记录文件写入也是阻塞操作
+                    let now = snapshot.taken_at.map(media_monitor::unix_millis).unwrap_or(0);
+                    history.lock().unwrap().observe(&snapshot.media_sessions, now);
+                    (sampler, snapshot)
+                }).await;
+
+                match result {
+                    Ok((s, snapshot)) => {
+                        sampler = s;
+                        sender.send_replace(Arc::new(snapshot));
+                    }
+                    Err(e) => {
+                        eprintln!("系统信息采集失败: {}", e);
+                        break;
+                    }
+                }
+
+                let interval = *interval.lock().unwrap();
+                tokio::time::sleep(interval.saturating_sub(started.elapsed())).await;
+            }
+        });
+
+        *self.handle.lock().unwrap() = Some(handle);
+    }
+
+    /// 修改采集间隔，下一轮采集后生效
+    pub fn set_interval(&self, interval: Duration) {
+        *self.interval.lock().unwrap() = interval.max(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    }
+
+    /// 获取最新快照
+    pub fn snapshot(&self) -> Arc<Snapshot> {
+        self.sender.borrow().clone()
+    }
+
+    /// 订阅快照更新，每次采集完成后收到通知
+    pub fn subscribe(&self) -> watch::Receiver<Arc<Snapshot>> {
+        self.sender.subscribe()
+    }
+
+    /// 等待首次采集完成
+    pub async fn ready(&self) {
+        let mut receiver = self.sender.subscribe();
+        let _ = receiver.wait_for(|snapshot| snapshot.taken_at.is_some()).await;
+    }
+}
@@ -0,0 +1,518 @@
+// 统一配置文件模块
+//
+// 所有设置保存在配置目录下的 config.json 中，带有版本号以便日后迁移结构。
+// 首次启动时会从旧版本的 remote_settings.json / media_settings.json 迁移。
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::fs;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::auth::AccessToken;
+use crate::media_monitor::MediaSettings;
+
+/// 当前配置文件结构版本
+pub const CONFIG_VERSION: u32 = 2;
+
+/// 串行化配置文件的读-改-写，避免并发保存互相覆盖
+static CONFIG_LOCK: Mutex<()> = Mutex::new(());
+
+/// 配置文件无法读取且未能备份时置位，此时保存会覆盖用户数据
+static SAVE_BLOCKED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpSettings {
+    pub port: u16,
+    pub bind_addresses: Vec<String>,  // 监听地址，"::" 表示 IPv6 双栈监听全部接口
+    pub require_auth: bool,           // 是否要求请求携带访问令牌
+    pub allowed_origins: Vec<String>, // 允许跨域读取 API 的网页来源，例如 https://example.com；为空时不允许跨域
+    pub allowed_hosts: Vec<String>,   // 除 IP 地址、localhost 和本机名外允许的 Host 头
+    #[serde(skip_deserializing)]
+    pub is_running: bool,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            port: 21536,
+            // 默认只监听本机，避免在局域网中暴露进程列表和窗口标题
+            bind_addresses: vec!["127.0.0.1".to_string()],
+            require_auth: false,
+            allowed_origins: Vec::new(),
+            allowed_hosts: Vec::new(),
+            is_running: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShareSettings {
+    pub share_computer_name: bool,
+    pub share_uptime: bool,
+    pub share_cpu_usage: bool,
+    pub share_memory_usage: bool,
+    pub share_processes: bool,
+    pub share_disks: bool,
+    pub share_network: bool,
+    pub share_battery: bool,
+    pub share_media: bool,
+    pub focused_process_only: bool,  // 进程列表只包含当前聚焦的进程（share_processes 开启时生效）
+}
+
+impl Default for ShareSettings {
+    fn default() -> Self {
+        Self {
+            share_computer_name: true,
+            share_uptime: true,
+            share_cpu_usage: true,
+            share_memory_usage: true,
+            share_processes: true,
+            share_disks: true,
+            share_network: true,
+            share_battery: true,
+            share_media: true,
+            focused_process_only: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub auto_start_http: bool,
+    pub auto_launch: bool,
+    pub silent_launch: bool,
+    pub process_limit: u32,
+    pub sample_interval_ms: u64,  // 后台采集间隔（毫秒）
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            auto_start_http: true,
+            auto_launch: false,
+            silent_launch: false,
+            process_limit: 20,
+            sample_interval_ms: 2000,
+        }
+    }
+}
+
+/// 推送请求体的压缩方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PushCompression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteSettings {
+    pub enabled: bool,
+    pub url: String,
+    pub token: String,
+    pub interval_seconds: u64,
+    pub queue_max_entries: usize,  // 离线队列最多保存的推送条数，超出时丢弃最旧的数据
+    pub batch_enabled: bool,       // 批量推送：按 interval_seconds 采样，攒够一批后一次上传
+    pub batch_upload_seconds: u64, // 批量模式下的上传间隔
+    pub batch_max_samples: usize,  // 批量模式下每次上传的最大条数，攒够后立即上传
+    pub batch_max_kb: usize,       // 批量模式下每次上传的最大大小（KB），攒够后立即上传
+    pub compression: PushCompression, // 请求体压缩方式，服务器返回 415 时改为发送未压缩的 JSON
+    pub delta_enabled: bool,       // 增量推送：只发送变化的分项，定期发送完整快照
+    pub delta_full_seconds: u64,   // 增量模式下发送完整快照的间隔
+}
+
+impl Default for RemoteSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            token: String::new(),
+            interval_seconds: 60,
+            queue_max_entries: 1440,
+            batch_enabled: false,
+            batch_upload_seconds: 60,
+            batch_max_samples: 20,
+            batch_max_kb: 512,
+            compression: PushCompression::None,
+            delta_enabled: false,
+            delta_full_seconds: 600,
+        }
+    }
+}
+
+/// 远程推送目标，每个目标由独立的推送任务负责
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PushTarget {
+    pub id: String,    // 唯一标识，也用于区分离线队列文件
+    pub name: String,
+    #[serde(flatten)]
+    pub remote: RemoteSettings,
+    pub share: Option<ShareSettings>,  // 覆盖全局共享设置，None 表示使用全局设置
+}
+
+impl Default for PushTarget {
+    fn default() -> Self {
+        Self {
+            id: DEFAULT_PUSH_TARGET_ID.to_string(),
+            name: "默认".to_string(),
+            remote: RemoteSettings::default(),
+            share: None,
+        }
+    }
+}
+
+/// 从单个远程设置迁移而来的推送目标的 id
+pub const DEFAULT_PUSH_TARGET_ID: &str = "default";
+
+/// 检查推送目标的 id：不能为空、不能重复，只能包含字母、数字、- 和 _
+pub fn validate_push_targets(targets: &[PushTarget]) -> Result<(), String> {
+    for (index, target) in targets.iter().enumerate() {
+        if target.id.is_empty()
+            || !target.id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!("无效的推送目标 id: {:?}", target.id));
+        }
+        if targets[..index].iter().any(|other| other.id == target.id) {
+            return Err(format!("推送目标 id 重复: {}", target.id));
+        }
+    }
+    Ok(())
+}
+
+/// 配置文件内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub version: u32,
+    pub http: HttpSettings,
+    pub share: ShareSettings,
+    pub app: AppSettings,
+    pub remote_targets: Vec<PushTarget>,
+    pub media: MediaSettings,
+    pub access_tokens: Vec<AccessToken>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            http: HttpSettings::default(),
+            share: ShareSettings::default(),
+            app: AppSettings::default(),
+            remote_targets: Vec::new(),
+            media: MediaSettings::default(),
+            access_tokens: Vec::new(),
+        }
+    }
+}
+
+// 获取配置目录
+pub fn get_config_dir() -> Result<PathBuf, String> {
+    let config_dir = dirs_next::config_dir()
+        .ok_or_else(|| "无法获取配置目录".to_string())?;
+
+    let app_config_dir = config_dir.join("watchmedo");
+
+    // 确保配置目录存在
+    if !app_config_dir.exists() {
+        fs::create_dir_all(&app_config_dir)
+            .map_err(|e| format!("无法创建配置目录: {}", e))?;
+    }
+
+    Ok(app_config_dir)
+}
+
+// 获取配置文件路径
+fn get_config_path() -> Result<PathBuf, String> {
+    Ok(get_config_dir()?.join("config.json"))
+}
+
+/// 加载配置，配置文件不存在时从旧版配置文件迁移
+pub fn load_config() -> Config {
+    let _guard = CONFIG_LOCK.lock().unwrap();
+    load_config_unlocked()
+}
+
+/// 保存完整配置
+pub fn save_config(config: &Config) -> Result<(), String> {
+    let _guard = CONFIG_LOCK.lock().unwrap();
+    save_config_unlocked(config)
+}
+
+/// 读取当前配置文件，修改后写回
+pub fn update_config<F: FnOnce(&mut Config)>(update: F) -> Result<(), String> {
+    let _guard = CONFIG_LOCK.lock().unwrap();
+    let mut config = load_config_unlocked();
+    update(&mut config);
+    save_config_unlocked(&config)
+}
+
+/// 按点分隔的路径读取配置项，例如 `remote.url`
+pub fn get_config_value(config: &Config, key: &str) -> Result<serde_json::Value, String> {
+    let value = serde_json::to_value(config)
+        .map_err(|e| format!("序列化配置失败: {}", e))?;
+
+    value.pointer(&config_pointer(key))
+        .cloned()
+        .ok_or_else(|| format!("未知的配置项: {}", key))
+}
+
+/// 按点分隔的路径修改配置项，字符串字段直接使用原文，其他字段按 JSON 解析
+pub fn set_config_value(config: &mut Config, key: &str, raw: &str) -> Result<(), String> {
+    let mut value = serde_json::to_value(&*config)
+        .map_err(|e| format!("序列化配置失败: {}", e))?;
+
+    let slot = value.pointer_mut(&config_pointer(key))
+        .ok_or_else(|| format!("未知的配置项: {}", key))?;
+
+    if slot.is_object() {
+        return Err(format!("{} 是配置分组，请指定具体的字段", key));
+    }
+
+    *slot = if slot.is_string() {
+        serde_json::Value::String(raw.to_string())
+    } else {
+        serde_json::from_str(raw).map_err(|_| format!("无效的值: {}", raw))?
+    };
+
+    let updated: Config = serde_json::from_value(value)
+        .map_err(|e| format!("无效的值 {}: {}", raw, e))?;
+    validate_push_targets(&updated.remote_targets)?;
+    *config = updated;
+
+    Ok(())
+}
+
+fn config_pointer(key: &str) -> String {
+    format!("/{}", key.replace('.', "/"))
+}
+
+fn load_config_unlocked() -> Config {
+    let path = match get_config_path() {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("获取配置文件路径失败: {}", e);
+            return Config::default();
+        }
+    };
+
+    if !path.exists() {
+        let (config, legacy_files) = migrate_legacy_files();
+        match save_config_unlocked(&config) {
+            Ok(()) => {
+                // 保留旧文件作为备份，之后不再读取
+                for legacy in legacy_files {
+                    let _ = fs::rename(&legacy, legacy.with_extension("json.bak"));
+                }
+            }
+            Err(e) => eprintln!("保存迁移后的配置失败: {}", e),
+        }
+        return config;
+    }
+
+    match read_config_file(&path) {
+        Ok(config) => {
+            SAVE_BLOCKED.store(false, Ordering::SeqCst);
+            config
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            quarantine_config_file(&path);
+            Config::default()
+        }
+    }
+}
+
+fn read_config_file(path: &Path) -> Result<Config, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("读取配置文件失败: {}", e))?;
+    let value = serde_json::from_str::<serde_json::Value>(&content)
+        .map_err(|e| format!("解析配置文件失败: {}", e))?;
+    let mut config = serde_json::from_value::<Config>(migrate(value))
+        .map_err(|e| format!("解析配置文件失败: {}", e))?;
+    config.version = CONFIG_VERSION;
+    Ok(config)
+}
+
+// 无法读取的配置文件改名为 config.json.corrupt-<时间戳>，之后保存默认配置时不会覆盖用户数据。
+// 改名失败时拒绝保存，直到配置文件能够正常读取
+fn quarantine_config_file(path: &Path) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let backup = path.with_file_name(format!("{}.corrupt-{}", file_name, timestamp));
+
+    match fs::rename(path, &backup) {
+        Ok(()) => {
+            eprintln!("已将无法读取的配置文件备份为 {}，使用默认配置", backup.display());
+            SAVE_BLOCKED.store(false, Ordering::SeqCst);
+        }
+        Err(e) => {
+            eprintln!("备份配置文件失败: {}，修复 {} 之前不会保存设置", e, path.display());
+            SAVE_BLOCKED.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+fn save_config_unlocked(config: &Config) -> Result<(), String> {
+    let path = get_config_path()?;
+    if SAVE_BLOCKED.load(Ordering::SeqCst) {
+        return Err(format!("配置文件 {} 无法读取且备份失败，为避免覆盖已拒绝保存", path.display()));
+    }
+    let json = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("序列化配置失败: {}", e))?;
+
+    // 先写入临时文件再重命名，保证配置文件不会被写坏
+    let tmp_path = path.with_extension("json.tmp");
+    let mut file = fs::File::create(&tmp_path)
+        .map_err(|e| format!("写入配置文件失败: {}", e))?;
+    file.write_all(json.as_bytes())
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("写入配置文件失败: {}", e))?;
+    drop(file);
+
+    fs::rename(&tmp_path, &path)
+        .map_err(|e| format!("写入配置文件失败: {}", e))?;
+
+    Ok(())
+}
+
+/// 将旧版本的配置结构升级到当前版本
+fn migrate(mut value: serde_json::Value) -> serde_json::Value {
+    let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+
+    if version > CONFIG_VERSION as u64 {
+        eprintln!("配置文件版本 {} 高于当前支持的版本 {}，尝试按当前版本解析", version, CONFIG_VERSION);
+    }
+
+    // 版本 0: 没有 version 字段，结构与版本 1 相同
+    if version < 1 {
+        if let Some(object) = value.as_object_mut() {
+            object.insert("version".to_string(), serde_json::Value::from(1));
+        }
+    }
+
+    // 版本 1 -> 2: 单个 remote 改为推送目标列表，原设置成为 id 为 default 的目标
+    if version < 2 {
+        if let Some(object) = value.as_object_mut() {
+            if let Some(remote) = object.remove("remote") {
+                let targets = serde_json::from_value::<RemoteSettings>(remote)
+                    .map(legacy_push_targets)
+                    .unwrap_or_default();
+                if let Ok(targets) = serde_json::to_value(targets) {
+                    object.insert("remote_targets".to_string(), targets);
+                }
+            }
+            object.insert("version".to_string(), serde_json::Value::from(2));
+        }
+    }
+
+    value
+}
+
+// 旧版的单个远程设置对应的推送目标，未配置 URL 时没有目标
+fn legacy_push_targets(remote: RemoteSettings) -> Vec<PushTarget> {
+    if remote.url.is_empty() {
+        return Vec::new();
+    }
+    vec![PushTarget {
+        remote,
+        ..Default::default()
+    }]
+}
+
+/// 从旧版本独立的配置文件构建配置，同时返回成功迁移的旧文件路径
+fn migrate_legacy_files() -> (Config, Vec<PathBuf>) {
+    let mut config = Config::default();
+    let mut legacy_files = Vec::new();
+
+    let config_dir = match get_config_dir() {
+        Ok(dir) => dir,
+        Err(_) => return (config, legacy_files),
+    };
+
+    let remote_path = config_dir.join("remote_settings.json");
+    if let Some(remote) = read_legacy_file::<RemoteSettings>(&remote_path) {
+        println!("从旧版配置迁移远程设置");
+        config.remote_targets = legacy_push_targets(remote);
+        legacy_files.push(remote_path);
+    }
+
+    let media_path = config_dir.join("media_settings.json");
+    if let Some(media) = read_legacy_file::<MediaSettings>(&media_path) {
+        println!("从旧版配置迁移媒体设置");
+        config.media = media;
+        legacy_files.push(media_path);
+    }
+
+    (config, legacy_files)
+}
+
+fn read_legacy_file<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+    let content = fs::read_to_string(path).ok()?;
+    match serde_json::from_str::<T>(&content) {
+        Ok(settings) => Some(settings),
+        Err(e) => {
+            eprintln!("解析旧版配置文件失败 {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quarantines_unreadable_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        fs::write(&path, "{ \"version\": 2, \"http\": ").unwrap();
+
+        assert!(read_config_file(&path).is_err());
+        quarantine_config_file(&path);
+        assert!(!path.exists());
+        assert!(!SAVE_BLOCKED.load(Ordering::SeqCst));
+
+        let backups: Vec<PathBuf> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(backups.len(), 1);
+        let name = backups[0].file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.starts_with("config.json.corrupt-"), "{}", name);
+        assert_eq!(fs::read_to_string(&backups[0]).unwrap(), "{ \"version\": 2, \"http\": ");
+    }
+
+    #[test]
+    fn rejects_config_with_wrong_types() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        fs::write(&path, r#"{ "version": 2, "http": { "port": "8080" } }"#).unwrap();
+        assert!(read_config_file(&path).is_err());
+    }
+
+    #[test]
+    fn migrates_v1_remote_to_default_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        fs::write(&path, r#"{ "version": 1, "remote": { "enabled": true, "url": "https://example.com/receive.php" } }"#).unwrap();
+
+        let config = read_config_file(&path).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.remote_targets.len(), 1);
+        assert_eq!(config.remote_targets[0].id, DEFAULT_PUSH_TARGET_ID);
+        assert_eq!(config.remote_targets[0].remote.url, "https://example.com/receive.php");
+        assert!(config.remote_targets[0].remote.enabled);
+    }
+}
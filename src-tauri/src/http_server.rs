@@ -0,0 +1,507 @@
+// 本地 HTTP API
+use std::sync::Arc;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::get,
+    Extension, Router, Json,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use socket2::{Domain, Protocol, Socket, Type};
+use tokio::sync::watch;
+use crate::AppState;
+use crate::auth::{ApiAccess, ApiSection};
+use crate::collector::Snapshot;
+use crate::config::ShareSettings;
+use crate::media_history::{MediaHistoryEntry, HISTORY_CAPACITY};
+use crate::media_monitor;
+use crate::metrics;
+use crate::stream;
+use crate::system_info::{build_system_info, build_system_info_with, ProcessQuery, ProcessSort, SystemInfo};
+
+/// /api/system 及分项接口的查询参数
+#[derive(Debug, Default, Deserialize)]
+struct SystemQuery {
+    fields: Option<String>,        // 逗号分隔的顶层字段，例如 cpu_usage,media
+    process_limit: Option<u32>,    // 覆盖设置中的进程数量限制
+    sort: Option<ProcessSort>,     // cpu | memory | name
+    name: Option<String>,          // 按进程名或窗口标题筛选
+}
+
+impl SystemQuery {
+    fn requested_fields(&self) -> Option<Vec<String>> {
+        self.fields.as_ref().map(|fields| {
+            fields
+                .split(',')
+                .map(|field| field.trim().to_string())
+                .filter(|field| !field.is_empty())
+                .collect()
+        })
+    }
+}
+
+/// SystemInfo 的顶层字段
+const SYSTEM_FIELDS: [&str; 10] = [
+    "computer_name",
+    "uptime",
+    "cpu_usage",
+    "memory_usage",
+    "processes",
+    "disks",
+    "network",
+    "battery",
+    "media",
+    "media_sessions",
+];
+
+// 根据共享设置和令牌范围，从快照构建请求可见的系统信息
+pub(crate) fn visible_system_info(state: &AppState, access: &ApiAccess, snapshot: &Snapshot) -> SystemInfo {
+    let share_settings = state.share_settings.lock().unwrap().clone();
+    let app_settings = state.app_settings.lock().unwrap().clone();
+
+    access.restrict(build_system_info(snapshot, &share_settings, app_settings.process_limit))
+}
+
+// 从最新快照构建当前请求可见的系统信息
+fn current_system_info(state: &AppState, access: &ApiAccess) -> SystemInfo {
+    visible_system_info(state, access, &state.collector.snapshot())
+}
+
+// 关闭未请求字段的共享开关，避免构建用不到的数据（例如完整进程列表）
+fn narrow_share_settings(share_settings: &mut ShareSettings, fields: &[String]) {
+    let wanted = |field: &str| fields.iter().any(|f| f == field);
+    share_settings.share_computer_name &= wanted("computer_name");
+    share_settings.share_uptime &= wanted("uptime");
+    share_settings.share_cpu_usage &= wanted("cpu_usage");
+    share_settings.share_memory_usage &= wanted("memory_usage");
+    share_settings.share_processes &= wanted("processes");
+    share_settings.share_disks &= wanted("disks");
+    share_settings.share_network &= wanted("network");
+    share_settings.share_battery &= wanted("battery");
+    share_settings.share_media &= wanted("media") || wanted("media_sessions");
+}
+
+// 按查询参数构建系统信息，只返回请求的字段
+fn query_system_info(state: &AppState, access: &ApiAccess, query: &SystemQuery) -> Result<Value, (StatusCode, String)> {
+    let mut share_settings = state.share_settings.lock().unwrap().clone();
+    let process_limit = state.app_settings.lock().unwrap().process_limit;
+
+    let fields = query.requested_fields();
+    if let Some(fields) = &fields {
+        if let Some(unknown) = fields.iter().find(|f| !SYSTEM_FIELDS.contains(&f.as_str())) {
+            return Err((StatusCode::BAD_REQUEST, format!("未知的字段: {}", unknown)));
+        }
+        narrow_share_settings(&mut share_settings, fields);
+    }
+
+    let process_query = ProcessQuery {
+        limit: query.process_limit.unwrap_or(process_limit),
+        sort: query.sort.unwrap_or_default(),
+        name: query.name.clone().filter(|name| !name.is_empty()),
+    };
+    let info = access.restrict(build_system_info_with(&state.collector.snapshot(), &share_settings, &process_query));
+
+    let mut all = match serde_json::to_value(info) {
+        Ok(Value::Object(all)) => all,
+        _ => return Err((StatusCode::INTERNAL_SERVER_ERROR, "序列化系统信息失败".to_string())),
+    };
+
+    Ok(match fields {
+        Some(fields) => Value::Object(
+            fields
+                .iter()
+                .filter_map(|field| all.remove_entry(field))
+                .collect::<Map<String, Value>>(),
+        ),
+        None => Value::Object(all),
+    })
+}
+
+async fn get_system_info(
+    State(state): State<Arc<AppState>>,
+    access: ApiAccess,
+    Query(query): Query<SystemQuery>,
+) -> Response {
+    match query_system_info(&state, &access, &query) {
+        Ok(info) => Json(info).into_response(),
+        Err(error) => error.into_response(),
+    }
+}
+
+/// GET /api/system/:section，只返回一个分项的数据
+async fn get_system_section(
+    State(state): State<Arc<AppState>>,
+    access: ApiAccess,
+    Path(section): Path<String>,
+    Query(mut query): Query<SystemQuery>,
+) -> Response {
+    let (field, allowed) = match section.as_str() {
+        "cpu" => ("cpu_usage", access.allows(ApiSection::Cpu)),
+        "memory" => ("memory_usage", access.allows(ApiSection::Memory)),
+        "processes" => ("processes", access.allows(ApiSection::Processes) || access.allows(ApiSection::FocusedProcess)),
+        "disks" => ("disks", access.allows(ApiSection::Disks)),
+        "network" => ("network", access.allows(ApiSection::Network)),
+        "battery" => ("battery", access.allows(ApiSection::Battery)),
+        "media" => ("media", access.allows(ApiSection::Media)),
+        "media_sessions" => ("media_sessions", access.allows(ApiSection::Media)),
+        _ => return (StatusCode::NOT_FOUND, format!("未知的分项: {}", section)).into_response(),
+    };
+
+    if !allowed {
+        return (StatusCode::FORBIDDEN, "访问令牌无权查看该数据").into_response();
+    }
+
+    query.fields = Some(field.to_string());
+    let value = match query_system_info(&state, &access, &query) {
+        Ok(mut info) => info[field].take(),
+        Err(error) => return error.into_response(),
+    };
+
+    // 电池和媒体为 null 可能只是当前没有该数据，按共享设置判断；其余分项为 null 表示未共享
+    let share_settings = state.share_settings.lock().unwrap().clone();
+    let shared = match field {
+        "battery" => share_settings.share_battery,
+        "media" => share_settings.share_media,
+        _ => !value.is_null(),
+    };
+    if !shared {
+        return (StatusCode::FORBIDDEN, "该数据未共享").into_response();
+    }
+
+    Json(value).into_response()
+}
+
+/// /api/media/thumbnail 的查询参数
+#[derive(Debug, Default, Deserialize)]
+struct ThumbnailQuery {
+    hash: Option<String>,  // MediaInfo.thumbnail_hash，省略时返回主会话的缩略图
+}
+
+/// GET /api/media/thumbnail，以二进制形式返回缩略图
+async fn get_media_thumbnail(
+    State(state): State<Arc<AppState>>,
+    access: ApiAccess,
+    Query(query): Query<ThumbnailQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if !access.allows(ApiSection::Media) {
+        return (StatusCode::FORBIDDEN, "访问令牌无权查看该数据").into_response();
+    }
+    if !state.share_settings.lock().unwrap().share_media {
+        return (StatusCode::FORBIDDEN, "该数据未共享").into_response();
+    }
+
+    // 指定哈希时内容不会变化，可以长期缓存；否则主会话切换后内容会变，每次都需要重新验证
+    let (hash, cache_control) = match query.hash {
+        Some(hash) => (hash, "private, max-age=31536000, immutable"),
+        None => {
+            let snapshot = state.collector.snapshot();
+            match snapshot.media_sessions.first().and_then(|media| media.thumbnail_hash.clone()) {
+                Some(hash) => (hash, "no-cache"),
+                None => return (StatusCode::NOT_FOUND, "当前没有缩略图").into_response(),
+            }
+        }
+    };
+
+    let Some(thumbnail) = media_monitor::find_thumbnail(&hash) else {
+        return (StatusCode::NOT_FOUND, "缩略图不存在或已过期").into_response();
+    };
+
+    let etag = format!("\"{}\"", thumbnail.hash);
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|tag| tag.trim().trim_start_matches("W/") == etag));
+
+    let response_headers = [
+        (header::ETAG, etag.clone()),
+        (header::CACHE_CONTROL, cache_control.to_string()),
+    ];
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, response_headers).into_response();
+    }
+
+    (
+        response_headers,
+        [(header::CONTENT_TYPE, thumbnail.content_type)],
+        thumbnail.data.clone(),
+    ).into_response()
+}
+
+/// /api/media/history 的查询参数
+#[derive(Debug, Default, Deserialize)]
+struct HistoryQuery {
+    limit: Option<usize>,  // 返回的记录数，默认 50
+}
+
+/// 最近的媒体播放记录
+pub fn media_history(state: &AppState, limit: Option<usize>) -> Vec<MediaHistoryEntry> {
+    let limit = limit.unwrap_or(50).min(HISTORY_CAPACITY);
+    let now = media_monitor::unix_millis(std::time::SystemTime::now());
+    state.media_history.lock().unwrap().recent(limit, now)
+}
+
+/// GET /api/media/history，最近播放的曲目，最新的在前
+async fn get_media_history(
+    State(state): State<Arc<AppState>>,
+    access: ApiAccess,
+    Query(query): Query<HistoryQuery>,
+) -> Response {
+    if !access.allows(ApiSection::Media) {
+        return (StatusCode::FORBIDDEN, "访问令牌无权查看该数据").into_response();
+    }
+    if !state.share_settings.lock().unwrap().share_media {
+        return (StatusCode::FORBIDDEN, "该数据未共享").into_response();
+    }
+
+    Json(media_history(&state, query.limit)).into_response()
+}
+
+async fn get_metrics(State(state): State<Arc<AppState>>, access: ApiAccess) -> impl IntoResponse {
+    let info = current_system_info(&state, &access);
+    ([(header::CONTENT_TYPE, metrics::CONTENT_TYPE)], metrics::render(&info))
+}
+
+/// 解析监听地址列表
+pub fn parse_bind_addresses(addresses: &[String]) -> Result<Vec<IpAddr>, String> {
+    if addresses.is_empty() {
+        return Err("未配置监听地址".to_string());
+    }
+
+    addresses
+        .iter()
+        .map(|addr| addr.trim().parse::<IpAddr>()
+            .map_err(|_| format!("无效的监听地址: {}", addr)))
+        .collect()
+}
+
+/// 解析允许跨域访问的来源，格式为 scheme://host[:port]
+pub fn parse_allowed_origins(origins: &[String]) -> Result<Vec<HeaderValue>, String> {
+    origins
+        .iter()
+        .map(|origin| {
+            let origin = origin.trim().trim_end_matches('/');
+            let valid = origin.starts_with("http://") || origin.starts_with("https://");
+            HeaderValue::from_str(origin)
+                .ok()
+                .filter(|_| valid)
+                .ok_or_else(|| format!("无效的来源: {}", origin))
+        })
+        .collect()
+}
+
+// 去掉 Host 头中的端口和 IPv6 地址的方括号
+fn host_name(host: &str) -> &str {
+    if let Some(rest) = host.strip_prefix('[') {
+        return rest.split(']').next().unwrap_or(rest);
+    }
+    host.rsplit_once(':')
+        .filter(|(_, port)| port.chars().all(|c| c.is_ascii_digit()))
+        .map_or(host, |(name, _)| name)
+}
+
+// Host 头是否可信：IP 地址、localhost、本机名或 allowed_hosts 中的主机名。
+// 其他域名可能是解析到本机的恶意域名（DNS 重绑定），即使只监听回环地址也要拒绝
+fn is_allowed_host(host: &str, allowed_hosts: &[String], computer_name: Option<&str>) -> bool {
+    let name = host_name(host).trim_end_matches('.').to_ascii_lowercase();
+    if name.parse::<IpAddr>().is_ok() || name == "localhost" || name.ends_with(".localhost") {
+        return true;
+    }
+    if allowed_hosts.iter().any(|allowed| allowed.trim().eq_ignore_ascii_case(&name)) {
+        return true;
+    }
+    computer_name.is_some_and(|computer| {
+        let computer = computer.to_ascii_lowercase();
+        name == computer || name == format!("{}.local", computer)
+    })
+}
+
+// 带 Origin 头的请求（跨域 fetch、WebSocket）只接受同源或 allowed_origins 中的来源。
+// 浏览器不会对 WebSocket 执行 CORS 检查，必须在这里拒绝其他网页的连接
+fn is_allowed_origin(origin: &str, host: Option<&str>, allowed_origins: &[String]) -> bool {
+    let same_origin = origin
+        .split_once("://")
+        .zip(host)
+        .is_some_and(|((_, origin_host), host)| origin_host.eq_ignore_ascii_case(host));
+    same_origin || allowed_origins.iter().any(|allowed| allowed.trim().trim_end_matches('/') == origin)
+}
+
+// 拒绝 Host 或 Origin 不可信的请求
+async fn check_request_source(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let http_settings = state.http_settings.lock().unwrap().clone();
+    let headers = request.headers();
+    let host = headers.get(header::HOST).and_then(|value| value.to_str().ok());
+
+    if let Some(host) = host {
+        let computer_name = hostname::get().ok().and_then(|name| name.into_string().ok());
+        if !is_allowed_host(host, &http_settings.allowed_hosts, computer_name.as_deref()) {
+            return (StatusCode::FORBIDDEN, "不允许的 Host").into_response();
+        }
+    }
+
+    if let Some(origin) = headers.get(header::ORIGIN) {
+        let allowed = origin
+            .to_str()
+            .is_ok_and(|origin| is_allowed_origin(origin, host, &http_settings.allowed_origins));
+        if !allowed {
+            return (StatusCode::FORBIDDEN, "不允许的来源").into_response();
+        }
+    }
+
+    next.run(request).await
+}
+
+// 创建 TCP 监听器，dual_stack 为 true 时 IPv6 套接字同时接受 IPv4 连接
+fn bind_listener(addr: SocketAddr, dual_stack: bool) -> std::io::Result<tokio::net::TcpListener> {
+    let domain = if addr.is_ipv6() { Domain::IPV6 } else { Domain::IPV4 };
+    let socket = Socket::new(domain, Type::STREAM, Some(Protocol::TCP))?;
+
+    if addr.is_ipv6() {
+        socket.set_only_v6(!dual_stack)?;
+    }
+    #[cfg(not(windows))]
+    socket.set_reuse_address(true)?;
+
+    socket.bind(&addr.into())?;
+    socket.listen(1024)?;
+    socket.set_nonblocking(true)?;
+
+    tokio::net::TcpListener::from_std(socket.into())
+}
+
+/// 启动 HTTP 服务器
+pub async fn start_http_server(state: AppState) -> Result<(), String> {
+    let http_settings = state.http_settings.lock().unwrap().clone();
+    let port = http_settings.port;
+    let ips = parse_bind_addresses(&http_settings.bind_addresses)?;
+    let allowed_origins = parse_allowed_origins(&http_settings.allowed_origins)?;
+
+    // 同时配置了 0.0.0.0 时，"::" 只监听 IPv6，避免端口冲突
+    let has_ipv4_any = ips.contains(&IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+
+    let mut listeners = Vec::with_capacity(ips.len());
+    for ip in ips {
+        let addr = SocketAddr::new(ip, port);
+        let dual_stack = ip.is_ipv6() && ip.is_unspecified() && !has_ipv4_any;
+        let listener = bind_listener(addr, dual_stack)
+            .map_err(|e| format!("无法监听 {}: {}", addr, e))?;
+        listeners.push(listener);
+    }
+
+    let app_state = Arc::new(state.clone());
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let shutdown = ServerShutdown(shutdown_rx);
+    
+    let app = Router::new()
+        .route("/api/system", get(get_system_info))
+        .route("/api/system/:section", get(get_system_section))
+        .route("/api/media/thumbnail", get(get_media_thumbnail))
+        .route("/api/media/history", get(get_media_history))
+        .route("/api/stream", get(stream::sse_handler))
+        .route("/api/ws", get(stream::ws_handler))
+        .route("/metrics", get(get_metrics))
+        .layer(middleware::from_fn_with_state(app_state.clone(), check_request_source))
+        .with_state(app_state.clone())
+        .layer(Extension(shutdown.clone()))
+        // 只有 allowed_origins 中的网页可以跨域读取；列表为空时不返回 CORS 头，浏览器会拦截跨域读取
+        .layer(
+            tower_http::cors::CorsLayer::new()
+                .allow_origin(tower_http::cors::AllowOrigin::list(allowed_origins))
+                .allow_methods([Method::GET])
+                .allow_headers([header::AUTHORIZATION]),
+        );
+
+    let handle = tokio::spawn(async move {
+        // 任务被中止时 JoinSet 随之释放，所有监听器一起停止
+        let mut servers = tokio::task::JoinSet::new();
+        for listener in listeners {
+            let app = app.clone();
+            let shutdown = shutdown.clone();
+            servers.spawn(async move {
+                if let Err(e) = axum::serve(listener, app).with_graceful_shutdown(shutdown.wait()).await {
+                    eprintln!("HTTP服务器异常退出: {}", e);
+                }
+            });
+        }
+        while servers.join_next().await.is_some() {}
+    });
+
+    *state.server_handle.lock().unwrap() = Some(handle);
+    *state.server_shutdown.lock().unwrap() = Some(shutdown_tx);
+    state.http_settings.lock().unwrap().is_running = true;
+
+    Ok(())
+}
+
+/// HTTP 服务器的停止信号。axum 的每个连接是独立的任务，停止监听后已建立的
+/// SSE / WebSocket 连接不会随之结束，需要等待这个信号自行关闭
+#[derive(Clone)]
+pub struct ServerShutdown(watch::Receiver<bool>);
+
+impl ServerShutdown {
+    /// 等到服务器停止；发送端被释放也视为停止
+    pub async fn wait(mut self) {
+        let _ = self.0.wait_for(|stopped| *stopped).await;
+    }
+}
+
+/// 停止 HTTP 服务器，并关闭所有已建立的连接
+pub fn stop_http_server(state: &AppState) {
+    if let Some(shutdown) = state.server_shutdown.lock().unwrap().take() {
+        let _ = shutdown.send(true);
+    }
+    if let Some(handle) = state.server_handle.lock().unwrap().take() {
+        handle.abort();
+    }
+    state.http_settings.lock().unwrap().is_running = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_ip_and_localhost_hosts() {
+        assert!(is_allowed_host("127.0.0.1:21536", &[], None));
+        assert!(is_allowed_host("[::1]:21536", &[], None));
+        assert!(is_allowed_host("192.168.1.20", &[], None));
+        assert!(is_allowed_host("localhost:21536", &[], None));
+        assert!(is_allowed_host("LOCALHOST.", &[], None));
+    }
+
+    #[test]
+    fn rejects_rebound_hosts() {
+        assert!(!is_allowed_host("evil.example:21536", &[], None));
+        assert!(!is_allowed_host("127.0.0.1.evil.example", &[], None));
+        assert!(is_allowed_host("evil.example", &["evil.example".to_string()], None));
+    }
+
+    #[test]
+    fn accepts_computer_name() {
+        assert!(is_allowed_host("MyPC:21536", &[], Some("mypc")));
+        assert!(is_allowed_host("mypc.local", &[], Some("MyPC")));
+        assert!(!is_allowed_host("otherpc", &[], Some("mypc")));
+    }
+
+    #[test]
+    fn checks_origin() {
+        let allowed = vec!["https://status.example.com/".to_string()];
+        assert!(is_allowed_origin("http://127.0.0.1:21536", Some("127.0.0.1:21536"), &[]));
+        assert!(is_allowed_origin("https://status.example.com", Some("127.0.0.1:21536"), &allowed));
+        assert!(!is_allowed_origin("https://evil.example", Some("127.0.0.1:21536"), &allowed));
+        assert!(!is_allowed_origin("null", Some("127.0.0.1:21536"), &allowed));
+        assert!(!is_allowed_origin("http://127.0.0.1:21536", None, &[]));
+    }
+
+    #[test]
+    fn parses_allowed_origins() {
+        let origins = parse_allowed_origins(&["https://example.com/".to_string()]).unwrap();
+        assert_eq!(origins, vec![HeaderValue::from_static("https://example.com")]);
+        assert!(parse_allowed_origins(&["example.com".to_string()]).is_err());
+        assert!(parse_allowed_origins(&["*".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+// WatchMeDo 核心库：系统信息采集、本地 HTTP API 与远程推送
+//
+// 桌面应用（Tauri）和无界面的 watchmedo-agent 共用这里的全部逻辑。
+pub mod auth;
+pub mod battery;
+pub mod collector;
+pub mod config;
+pub mod http_server;
+#[cfg(target_os = "linux")]
+mod linux_windows;
+pub mod media_history;
+pub mod media_monitor;
+pub mod metrics;
+pub mod push_delta;
+pub mod push_queue;
+pub mod remote_push;
+pub mod stream;
+pub mod system_info;
+mod windows_helper;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use auth::AccessToken;
+use collector::Collector;
+use config::{Config, HttpSettings, ShareSettings, AppSettings, PushTarget};
+use media_history::MediaHistory;
+use media_monitor::MediaSettings;
+use remote_push::PushStatus;
+
+#[derive(Clone)]
+pub struct AppState {
+    pub http_settings: Arc<Mutex<HttpSettings>>,
+    pub share_settings: Arc<Mutex<ShareSettings>>,
+    pub app_settings: Arc<Mutex<AppSettings>>,
+    pub remote_targets: Arc<Mutex<Vec<PushTarget>>>,
+    pub media_settings: Arc<Mutex<MediaSettings>>,
+    pub media_history: Arc<Mutex<MediaHistory>>,
+    pub access_tokens: Arc<Mutex<Vec<AccessToken>>>,
+    pub server_handle: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    pub server_shutdown: Arc<Mutex<Option<tokio::sync::watch::Sender<bool>>>>,  // 停止 HTTP 服务器时通知已建立的实时推送连接
+    pub remote_push_handles: Arc<Mutex<HashMap<String, tokio::task::JoinHandle<()>>>>,  // 按推送目标 id
+    pub push_status: Arc<Mutex<HashMap<String, PushStatus>>>,                          // 按推送目标 id
+    pub collector: Collector,
+}
+
+impl AppState {
+    pub fn new(config: &Config) -> Self {
+        let media_settings = Arc::new(Mutex::new(config.media.clone()));
+        let media_history = Arc::new(Mutex::new(MediaHistory::load()));
+
+        Self {
+            collector: Collector::new(
+                Duration::from_millis(config.app.sample_interval_ms),
+                media_settings.clone(),
+                media_history.clone(),
+            ),
+            http_settings: Arc::new(Mutex::new(config.http.clone())),
+            share_settings: Arc::new(Mutex::new(config.share.clone())),
+            app_settings: Arc::new(Mutex::new(config.app.clone())),
+            remote_targets: Arc::new(Mutex::new(config.remote_targets.clone())),
+            media_settings,
+            media_history,
+            access_tokens: Arc::new(Mutex::new(config.access_tokens.clone())),
+            server_handle: Arc::new(Mutex::new(None)),
+            server_shutdown: Arc::new(Mutex::new(None)),
+            remote_push_handles: Arc::new(Mutex::new(HashMap::new())),
+            push_status: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 结束正在跟踪的播放记录，程序退出前调用
+    pub fn finish_media_history(&self) {
+        let now = media_monitor::unix_millis(SystemTime::now());
+        self.media_history.lock().unwrap().finish_all(now);
+    }
+
+    /// 将一个分项的当前设置写入配置文件，其余分项保持文件中的内容
+    pub fn save_settings(&self, section: SettingsSection) -> Result<(), String> {
+        match section {
+            SettingsSection::Http => {
+                let http = self.http_settings.lock().unwrap().clone();
+                config::update_config(|config| config.http = http)
+            }
+            SettingsSection::Share => {
+                let share = self.share_settings.lock().unwrap().clone();
+                config::update_config(|config| config.share = share)
+            }
+            SettingsSection::App => {
+                let app = self.app_settings.lock().unwrap().clone();
+                config::update_config(|config| config.app = app)
+            }
+            SettingsSection::RemoteTargets => {
+                let remote_targets = self.remote_targets.lock().unwrap().clone();
+                config::update_config(|config| config.remote_targets = remote_targets)
+            }
+            SettingsSection::Media => {
+                let media = self.media_settings.lock().unwrap().clone();
+                config::update_config(|config| config.media = media)
+            }
+            SettingsSection::AccessTokens => {
+                let access_tokens = self.access_tokens.lock().unwrap().clone();
+                config::update_config(|config| config.access_tokens = access_tokens)
+            }
+        }
+    }
+}
+
+/// 配置文件中的分项。运行中的程序只在启动时读取配置，保存时只写入被修改的分项，
+/// 不会覆盖之后通过 `watchmedo-agent config set` 对其他分项的修改
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsSection {
+    Http,
+    Share,
+    App,
+    RemoteTargets,
+    Media,
+    AccessTokens,
+}
@@ -0,0 +1,158 @@
+// Hyprland 后端
+//
+// 每个请求单独连接 `.socket.sock`，写入命令后读取到连接关闭为止。
+// 命令前缀 `j/` 表示返回 JSON：`activewindow` 返回聚焦窗口，`clients` 返回所有窗口。
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+use serde_json::Value;
+use super::WindowBackend;
+
+const IPC_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 根据 HYPRLAND_INSTANCE_SIGNATURE 查找套接字，新版本位于 $XDG_RUNTIME_DIR/hypr，旧版本位于 /tmp/hypr
+pub fn socket_path() -> Option<PathBuf> {
+    let signature = std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE")?;
+
+    let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from);
+    runtime_dir
+        .into_iter()
+        .chain(std::iter::once(PathBuf::from("/tmp")))
+        .map(|dir| dir.join("hypr").join(&signature).join(".socket.sock"))
+        .find(|path| path.exists())
+}
+
+/// 通过 Hyprland IPC 套接字读取窗口信息
+pub struct HyprlandBackend {
+    socket_path: PathBuf,
+}
+
+impl HyprlandBackend {
+    pub fn new(socket_path: PathBuf) -> Self {
+        Self { socket_path }
+    }
+
+    fn request(&self, command: &str) -> Result<Value, String> {
+        let mut stream = UnixStream::connect(&self.socket_path)
+            .map_err(|e| format!("连接 Hyprland IPC 失败: {}", e))?;
+        stream.set_read_timeout(Some(IPC_TIMEOUT))
+            .and_then(|_| stream.set_write_timeout(Some(IPC_TIMEOUT)))
+            .map_err(|e| format!("连接 Hyprland IPC 失败: {}", e))?;
+
+        stream.write_all(format!("j/{}", command).as_bytes())
+            .map_err(|e| format!("发送 Hyprland IPC 请求失败: {}", e))?;
+
+        let mut body = Vec::new();
+        stream.read_to_end(&mut body)
+            .map_err(|e| format!("读取 Hyprland IPC 响应失败: {}", e))?;
+
+        serde_json::from_slice(&body).map_err(|e| format!("解析 Hyprland IPC 响应失败: {}", e))
+    }
+}
+
+fn window_pid(window: &Value) -> Option<u32> {
+    // 没有聚焦窗口时 activewindow 返回 {}，部分特殊窗口的 pid 为 -1
+    window.get("pid").and_then(Value::as_i64).filter(|pid| *pid > 0).map(|pid| pid as u32)
+}
+
+impl WindowBackend for HyprlandBackend {
+    fn focused_pid(&self) -> Result<Option<u32>, String> {
+        Ok(window_pid(&self.request("activewindow")?))
+    }
+
+    fn window_titles(&self) -> Result<HashMap<u32, String>, String> {
+        let clients = self.request("clients")?;
+        let mut titles = HashMap::new();
+
+        for client in clients.as_array().into_iter().flatten() {
+            if client.get("mapped").and_then(Value::as_bool) == Some(false) {
+                continue;
+            }
+            let (Some(pid), Some(title)) = (window_pid(client), client.get("title").and_then(Value::as_str)) else {
+                continue;
+            };
+            if !title.is_empty() {
+                titles.entry(pid).or_insert_with(|| title.to_string());
+            }
+        }
+
+        Ok(titles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::path::Path;
+    use std::thread::JoinHandle;
+
+    // 模拟 Hyprland：按顺序校验命令并返回对应响应，写完即关闭连接
+    fn serve(path: &Path, replies: Vec<(&'static str, &'static str)>) -> JoinHandle<()> {
+        let listener = UnixListener::bind(path).unwrap();
+        std::thread::spawn(move || {
+            for (command, reply) in replies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buffer = [0u8; 64];
+                let read = stream.read(&mut buffer).unwrap();
+                assert_eq!(&buffer[..read], command.as_bytes());
+                stream.write_all(reply.as_bytes()).unwrap();
+            }
+        })
+    }
+
+    #[test]
+    fn reads_active_window_and_clients() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".socket.sock");
+        let server = serve(&path, vec![
+            ("j/activewindow", r#"{"address": "0x1", "pid": 200, "title": "终端", "mapped": true}"#),
+            ("j/clients", r#"[
+                {"address": "0x1", "pid": 200, "title": "终端", "mapped": true},
+                {"address": "0x2", "pid": 100, "title": "Firefox", "mapped": true},
+                {"address": "0x3", "pid": 100, "title": "Firefox - 第二个窗口", "mapped": true},
+                {"address": "0x4", "pid": 300, "title": "隐藏窗口", "mapped": false},
+                {"address": "0x5", "pid": -1, "title": "特殊窗口", "mapped": true},
+                {"address": "0x6", "pid": 400, "title": "", "mapped": true}
+            ]"#),
+        ]);
+
+        let backend = HyprlandBackend::new(path);
+        assert_eq!(backend.focused_pid().unwrap(), Some(200));
+
+        let titles = backend.window_titles().unwrap();
+        assert_eq!(titles.len(), 2);
+        assert_eq!(titles[&200], "终端");
+        assert_eq!(titles[&100], "Firefox");
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn empty_or_special_active_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".socket.sock");
+        let server = serve(&path, vec![
+            ("j/activewindow", "{}"),
+            ("j/activewindow", r#"{"address": "0x5", "pid": -1, "title": "特殊窗口"}"#),
+        ]);
+
+        let backend = HyprlandBackend::new(path);
+        assert_eq!(backend.focused_pid().unwrap(), None);
+        assert_eq!(backend.focused_pid().unwrap(), None);
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn invalid_reply_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".socket.sock");
+        let server = serve(&path, vec![("j/clients", "unknown request")]);
+
+        assert!(HyprlandBackend::new(path).window_titles().is_err());
+        server.join().unwrap();
+    }
+}
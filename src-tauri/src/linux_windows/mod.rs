@@ -0,0 +1,137 @@
+// Linux 前台窗口与窗口标题检测
+//
+// 按运行环境自动选择后端：Hyprland（HYPRLAND_INSTANCE_SIGNATURE）、sway（SWAYSOCK），
+// 否则通过 X11 的 EWMH 属性读取。Wayland 下 X11 只能看到 XWayland 窗口，因此合成器后端优先。
+// 后端通过套接字路径构造，可以指向测试用的假服务器。
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+mod hyprland;
+mod sway;
+mod x11;
+
+/// 窗口信息后端
+pub(crate) trait WindowBackend {
+    /// 当前聚焦窗口所属的进程
+    fn focused_pid(&self) -> Result<Option<u32>, String>;
+    /// 每个进程第一个非空窗口标题
+    fn window_titles(&self) -> Result<HashMap<u32, String>, String>;
+}
+
+// 根据环境变量选择后端
+fn detect_backend() -> Box<dyn WindowBackend + Send> {
+    if let Some(path) = hyprland::socket_path() {
+        return Box::new(hyprland::HyprlandBackend::new(path));
+    }
+    if let Some(path) = std::env::var_os("SWAYSOCK") {
+        return Box::new(sway::SwayBackend::new(PathBuf::from(path)));
+    }
+    Box::new(x11::X11Backend)
+}
+
+type BackendSlot = Mutex<Option<Box<dyn WindowBackend + Send>>>;
+
+// 检测到的后端在采样之间复用，出错时丢弃，下一次采样重新检测（例如合成器重启后套接字路径变化）
+static BACKEND: BackendSlot = Mutex::new(None);
+
+// 每种查询是否处于失败状态，连续失败时只输出一次日志
+static FOCUS_FAILED: AtomicBool = AtomicBool::new(false);
+static TITLES_FAILED: AtomicBool = AtomicBool::new(false);
+
+fn query<T: Default>(
+    slot: &BackendSlot,
+    detect: impl FnOnce() -> Box<dyn WindowBackend + Send>,
+    failed: &AtomicBool,
+    what: &str,
+    run: impl FnOnce(&dyn WindowBackend) -> Result<T, String>,
+) -> T {
+    let mut backend = slot.lock().unwrap();
+    let result = run(backend.get_or_insert_with(detect).as_ref());
+
+    match result {
+        Ok(value) => {
+            if failed.swap(false, Ordering::Relaxed) {
+                println!("{}已恢复", what);
+            }
+            value
+        }
+        Err(e) => {
+            *backend = None;
+            if !failed.swap(true, Ordering::Relaxed) {
+                eprintln!("{}失败: {}", what, e);
+            }
+            T::default()
+        }
+    }
+}
+
+pub fn get_focused_pid() -> Option<u32> {
+    query(&BACKEND, detect_backend, &FOCUS_FAILED, "获取聚焦窗口", |backend| backend.focused_pid())
+}
+
+pub fn get_window_titles() -> HashMap<u32, String> {
+    query(&BACKEND, detect_backend, &TITLES_FAILED, "获取窗口标题", |backend| backend.window_titles())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    type FocusResult = Result<Option<u32>, String>;
+
+    // 按预设结果依次返回的后端
+    struct FakeBackend {
+        results: Arc<Mutex<Vec<FocusResult>>>,
+    }
+
+    impl WindowBackend for FakeBackend {
+        fn focused_pid(&self) -> FocusResult {
+            self.results.lock().unwrap().remove(0)
+        }
+
+        fn window_titles(&self) -> Result<HashMap<u32, String>, String> {
+            Ok(HashMap::new())
+        }
+    }
+
+    #[test]
+    fn reuses_backend_until_it_fails() {
+        let slot: BackendSlot = Mutex::new(None);
+        let failed = AtomicBool::new(false);
+        let detected = AtomicUsize::new(0);
+        let results = Arc::new(Mutex::new(vec![
+            Ok(Some(1)),
+            Ok(Some(2)),
+            Err("连接已断开".to_string()),
+            Err("连接被拒绝".to_string()),
+            Ok(Some(3)),
+        ]));
+
+        let focused_pid = || {
+            let detect = || {
+                detected.fetch_add(1, Ordering::SeqCst);
+                Box::new(FakeBackend { results: results.clone() }) as Box<dyn WindowBackend + Send>
+            };
+            query(&slot, detect, &failed, "测试", |backend| backend.focused_pid())
+        };
+
+        assert_eq!(focused_pid(), Some(1));
+        assert_eq!(focused_pid(), Some(2));
+        assert_eq!(detected.load(Ordering::SeqCst), 1);
+
+        // 出错后丢弃后端，下一次查询重新检测
+        assert_eq!(focused_pid(), None);
+        assert!(failed.load(Ordering::SeqCst));
+        assert!(slot.lock().unwrap().is_none());
+        assert_eq!(focused_pid(), None);
+        assert_eq!(detected.load(Ordering::SeqCst), 2);
+
+        assert_eq!(focused_pid(), Some(3));
+        assert!(!failed.load(Ordering::SeqCst));
+        assert_eq!(detected.load(Ordering::SeqCst), 3);
+    }
+}
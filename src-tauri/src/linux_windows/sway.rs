@@ -0,0 +1,193 @@
+// sway 后端（i3 IPC 协议）
+//
+// 消息格式：`i3-ipc` 魔数 + 载荷长度（u32，本机字节序）+ 消息类型（u32）+ JSON 载荷。
+// GET_TREE 返回完整的容器树，叶子节点带有 pid、name（窗口标题）和 focused。
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+use serde_json::Value;
+use super::WindowBackend;
+
+const MAGIC: &[u8] = b"i3-ipc";
+const GET_TREE: u32 = 4;
+const IPC_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 通过 sway IPC 套接字读取窗口信息
+pub struct SwayBackend {
+    socket_path: PathBuf,
+}
+
+impl SwayBackend {
+    pub fn new(socket_path: PathBuf) -> Self {
+        Self { socket_path }
+    }
+
+    fn request(&self, message_type: u32, payload: &[u8]) -> Result<Value, String> {
+        let mut stream = UnixStream::connect(&self.socket_path)
+            .map_err(|e| format!("连接 sway IPC 失败: {}", e))?;
+        stream.set_read_timeout(Some(IPC_TIMEOUT))
+            .and_then(|_| stream.set_write_timeout(Some(IPC_TIMEOUT)))
+            .map_err(|e| format!("连接 sway IPC 失败: {}", e))?;
+
+        let mut message = Vec::with_capacity(MAGIC.len() + 8 + payload.len());
+        message.extend_from_slice(MAGIC);
+        message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+        message.extend_from_slice(&message_type.to_ne_bytes());
+        message.extend_from_slice(payload);
+        stream.write_all(&message)
+            .map_err(|e| format!("发送 sway IPC 请求失败: {}", e))?;
+
+        let mut header = [0u8; 14];
+        stream.read_exact(&mut header)
+            .map_err(|e| format!("读取 sway IPC 响应失败: {}", e))?;
+        if &header[..6] != MAGIC {
+            return Err("无效的 sway IPC 响应".to_string());
+        }
+        let length = u32::from_ne_bytes([header[6], header[7], header[8], header[9]]) as usize;
+
+        let mut body = vec![0u8; length];
+        stream.read_exact(&mut body)
+            .map_err(|e| format!("读取 sway IPC 响应失败: {}", e))?;
+
+        serde_json::from_slice(&body).map_err(|e| format!("解析 sway IPC 响应失败: {}", e))
+    }
+
+    // 深度优先遍历容器树，返回所有带 pid 的窗口节点
+    fn windows(&self) -> Result<Vec<Value>, String> {
+        let tree = self.request(GET_TREE, b"")?;
+        let mut windows = Vec::new();
+        let mut stack = vec![tree];
+
+        while let Some(mut node) = stack.pop() {
+            for key in ["floating_nodes", "nodes"] {
+                if let Some(Value::Array(children)) = node.get_mut(key).map(Value::take) {
+                    stack.extend(children.into_iter().rev());
+                }
+            }
+            if node.get("pid").and_then(Value::as_u64).is_some() {
+                windows.push(node);
+            }
+        }
+
+        Ok(windows)
+    }
+}
+
+fn window_pid(window: &Value) -> Option<u32> {
+    window.get("pid").and_then(Value::as_u64).map(|pid| pid as u32).filter(|pid| *pid != 0)
+}
+
+impl WindowBackend for SwayBackend {
+    fn focused_pid(&self) -> Result<Option<u32>, String> {
+        Ok(self.windows()?
+            .iter()
+            .find(|window| window.get("focused").and_then(Value::as_bool) == Some(true))
+            .and_then(window_pid))
+    }
+
+    fn window_titles(&self) -> Result<HashMap<u32, String>, String> {
+        let mut titles = HashMap::new();
+        for window in self.windows()? {
+            let (Some(pid), Some(title)) = (window_pid(&window), window.get("name").and_then(Value::as_str)) else {
+                continue;
+            };
+            if !title.is_empty() {
+                titles.entry(pid).or_insert_with(|| title.to_string());
+            }
+        }
+        Ok(titles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::path::Path;
+    use std::thread::JoinHandle;
+
+    // 模拟 sway：对每个连接校验 GET_TREE 请求并返回同一棵容器树
+    fn serve_tree(path: &Path, tree: Value, connections: usize) -> JoinHandle<()> {
+        let listener = UnixListener::bind(path).unwrap();
+        std::thread::spawn(move || {
+            let body = serde_json::to_vec(&tree).unwrap();
+            for _ in 0..connections {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut header = [0u8; 14];
+                stream.read_exact(&mut header).unwrap();
+                assert_eq!(&header[..6], MAGIC);
+                assert_eq!(u32::from_ne_bytes([header[6], header[7], header[8], header[9]]), 0);
+                assert_eq!(u32::from_ne_bytes([header[10], header[11], header[12], header[13]]), GET_TREE);
+
+                let mut reply = MAGIC.to_vec();
+                reply.extend_from_slice(&(body.len() as u32).to_ne_bytes());
+                reply.extend_from_slice(&GET_TREE.to_ne_bytes());
+                reply.extend_from_slice(&body);
+                stream.write_all(&reply).unwrap();
+            }
+        })
+    }
+
+    #[test]
+    fn reads_tiled_and_nested_floating_windows() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sway-ipc.sock");
+        let tree = serde_json::json!({
+            "type": "root",
+            "nodes": [{
+                "type": "output",
+                "name": "eDP-1",
+                "nodes": [{
+                    "type": "workspace",
+                    "name": "1",
+                    "nodes": [
+                        { "type": "con", "pid": 100, "name": "Firefox", "focused": false },
+                        { "type": "con", "pid": 100, "name": "Firefox - 第二个窗口", "focused": false },
+                        { "type": "con", "pid": 0, "name": "无进程", "focused": false },
+                        { "type": "con", "pid": 400, "name": "", "focused": false },
+                    ],
+                    "floating_nodes": [{
+                        "type": "floating_con",
+                        "nodes": [
+                            { "type": "con", "pid": 300, "name": "悬浮终端", "focused": true },
+                        ],
+                    }],
+                }],
+            }],
+        });
+        let server = serve_tree(&path, tree, 2);
+
+        let backend = SwayBackend::new(path);
+        assert_eq!(backend.focused_pid().unwrap(), Some(300));
+
+        let titles = backend.window_titles().unwrap();
+        assert_eq!(titles.len(), 2);
+        assert_eq!(titles[&100], "Firefox");
+        assert_eq!(titles[&300], "悬浮终端");
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn no_focused_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sway-ipc.sock");
+        let tree = serde_json::json!({
+            "type": "root",
+            "focused": true,
+            "nodes": [{ "type": "con", "pid": 100, "name": "Firefox", "focused": false }],
+        });
+        let server = serve_tree(&path, tree, 1);
+
+        assert_eq!(SwayBackend::new(path).focused_pid().unwrap(), None);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn missing_socket_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(SwayBackend::new(dir.path().join("missing.sock")).focused_pid().is_err());
+    }
+}
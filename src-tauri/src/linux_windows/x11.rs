@@ -0,0 +1,199 @@
+// X11 / EWMH 后端
+//
+// 根窗口的 _NET_ACTIVE_WINDOW 给出当前聚焦的窗口，_NET_CLIENT_LIST 给出所有顶层窗口；
+// 每个窗口的 _NET_WM_PID 和 _NET_WM_NAME（UTF-8，缺失时回退到 WM_NAME）给出进程和标题。
+// 连接在两次采集之间复用，出错时丢弃并在下一次采集时重连。
+use std::collections::HashMap;
+use std::sync::Mutex;
+use x11rb::connection::Connection;
+use x11rb::errors::ReplyError;
+use x11rb::protocol::xproto::{Atom, AtomEnum, ConnectionExt, Window};
+use x11rb::rust_connection::RustConnection;
+use super::WindowBackend;
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        _NET_ACTIVE_WINDOW,
+        _NET_CLIENT_LIST,
+        _NET_WM_PID,
+        _NET_WM_NAME,
+        UTF8_STRING,
+    }
+}
+
+struct X11Session {
+    conn: RustConnection,
+    root: Window,
+    atoms: Atoms,
+}
+
+static SESSION: Mutex<Option<X11Session>> = Mutex::new(None);
+
+impl X11Session {
+    fn connect() -> Option<Self> {
+        // 连接失败不输出日志：没有 X 服务器时每次采集都会失败
+        let (conn, screen_num) = x11rb::connect(None).ok()?;
+        let root = conn.setup().roots.get(screen_num)?.root;
+        let atoms = Atoms::new(&conn).ok()?.reply().ok()?;
+        Some(Self { conn, root, atoms })
+    }
+
+    // 读取 32 位格式的属性值
+    fn property_u32(&self, window: Window, property: Atom, kind: impl Into<Atom>) -> Result<Vec<u32>, ReplyError> {
+        let reply = self.conn
+            .get_property(false, window, property, kind, 0, u32::MAX / 4)?
+            .reply()?;
+        Ok(reply.value32().map(|values| values.collect()).unwrap_or_default())
+    }
+
+    fn window_pid(&self, window: Window) -> Result<Option<u32>, ReplyError> {
+        let pid = self.property_u32(window, self.atoms._NET_WM_PID, AtomEnum::CARDINAL)?;
+        Ok(pid.first().copied().filter(|pid| *pid != 0))
+    }
+
+    fn window_title(&self, window: Window) -> Result<Option<String>, ReplyError> {
+        for (property, kind) in [
+            (self.atoms._NET_WM_NAME, self.atoms.UTF8_STRING),
+            (AtomEnum::WM_NAME.into(), AtomEnum::STRING.into()),
+        ] {
+            let reply = self.conn
+                .get_property(false, window, property, kind, 0, u32::MAX / 4)?
+                .reply()?;
+            let title = String::from_utf8_lossy(&reply.value).trim().to_string();
+            if !title.is_empty() {
+                return Ok(Some(title));
+            }
+        }
+        Ok(None)
+    }
+
+    fn focused_pid(&self) -> Result<Option<u32>, ReplyError> {
+        let active = self.property_u32(self.root, self.atoms._NET_ACTIVE_WINDOW, AtomEnum::WINDOW)?;
+        match active.first().copied() {
+            Some(window) if window != 0 => self.window_pid(window),
+            _ => Ok(None),
+        }
+    }
+
+    fn window_titles(&self) -> Result<HashMap<u32, String>, ReplyError> {
+        let mut titles = HashMap::new();
+
+        for window in self.property_u32(self.root, self.atoms._NET_CLIENT_LIST, AtomEnum::WINDOW)? {
+            // 窗口可能在枚举过程中被关闭，单个窗口出错时跳过
+            let (Ok(Some(pid)), Ok(Some(title))) = (self.window_pid(window), self.window_title(window)) else {
+                continue;
+            };
+            // Store the first non-empty title for each PID
+            titles.entry(pid).or_insert(title);
+        }
+
+        Ok(titles)
+    }
+}
+
+// 在共享连接上执行查询，没有可用的 X 服务器时返回 Ok(None)
+fn with_session<T>(query: impl FnOnce(&X11Session) -> Result<T, ReplyError>) -> Result<Option<T>, String> {
+    if std::env::var_os("DISPLAY").is_none() {
+        return Ok(None);
+    }
+
+    let mut session = SESSION.lock().unwrap();
+    if session.is_none() {
+        *session = X11Session::connect();
+    }
+    let Some(connected) = session.as_ref() else {
+        return Ok(None);
+    };
+
+    match query(connected) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) => {
+            *session = None;
+            Err(format!("读取 X11 窗口信息失败: {}", e))
+        }
+    }
+}
+
+/// 通过 X11 / EWMH 读取窗口信息
+pub struct X11Backend;
+
+impl WindowBackend for X11Backend {
+    fn focused_pid(&self) -> Result<Option<u32>, String> {
+        with_session(X11Session::focused_pid).map(Option::flatten)
+    }
+
+    fn window_titles(&self) -> Result<HashMap<u32, String>, String> {
+        with_session(X11Session::window_titles).map(Option::unwrap_or_default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x11rb::protocol::xproto::{CreateWindowAux, PropMode, WindowClass};
+    use x11rb::wrapper::ConnectionExt as _;
+    use x11rb::COPY_DEPTH_FROM_PARENT;
+
+    // 代替窗口管理器创建两个带 EWMH 属性的窗口，并设置根窗口的 _NET_CLIENT_LIST / _NET_ACTIVE_WINDOW。
+    // 会修改根窗口属性，只应在 Xvfb 中运行
+    #[test]
+    #[ignore = "需要 Xvfb：xvfb-run cargo test --no-default-features -- --ignored x11"]
+    fn reads_focused_pid_and_titles_from_ewmh() {
+        if std::env::var_os("DISPLAY").is_none() {
+            eprintln!("未设置 DISPLAY，跳过 X11 测试");
+            return;
+        }
+
+        let (conn, screen_num) = x11rb::connect(None).expect("连接 X 服务器失败");
+        let root = conn.setup().roots[screen_num].root;
+        let atoms = Atoms::new(&conn).unwrap().reply().unwrap();
+
+        let mut windows = Vec::new();
+        for pid in [4242u32, 4343] {
+            let window = conn.generate_id().unwrap();
+            conn.create_window(
+                COPY_DEPTH_FROM_PARENT,
+                window,
+                root,
+                0, 0, 100, 100, 0,
+                WindowClass::INPUT_OUTPUT,
+                0,
+                &CreateWindowAux::new(),
+            ).unwrap();
+            conn.change_property32(PropMode::REPLACE, window, atoms._NET_WM_PID, AtomEnum::CARDINAL, &[pid]).unwrap();
+            windows.push(window);
+        }
+        // 第一个窗口使用 UTF-8 的 _NET_WM_NAME，第二个窗口只有 WM_NAME
+        conn.change_property8(PropMode::REPLACE, windows[0], atoms._NET_WM_NAME, atoms.UTF8_STRING, "编辑器 - main.rs".as_bytes()).unwrap();
+        conn.change_property8(PropMode::REPLACE, windows[1], AtomEnum::WM_NAME, AtomEnum::STRING, b"Terminal").unwrap();
+
+        conn.change_property32(PropMode::REPLACE, root, atoms._NET_CLIENT_LIST, AtomEnum::WINDOW, &windows).unwrap();
+        conn.change_property32(PropMode::REPLACE, root, atoms._NET_ACTIVE_WINDOW, AtomEnum::WINDOW, &[windows[1]]).unwrap();
+        conn.sync().unwrap();
+
+        let backend = X11Backend;
+        assert_eq!(backend.focused_pid().unwrap(), Some(4343));
+
+        let titles = backend.window_titles().unwrap();
+        assert_eq!(titles.len(), 2);
+        assert_eq!(titles[&4242], "编辑器 - main.rs");
+        assert_eq!(titles[&4343], "Terminal");
+
+        // 切换焦点后复用同一个连接读取
+        conn.change_property32(PropMode::REPLACE, root, atoms._NET_ACTIVE_WINDOW, AtomEnum::WINDOW, &[windows[0]]).unwrap();
+        conn.sync().unwrap();
+        assert_eq!(backend.focused_pid().unwrap(), Some(4242));
+
+        // 没有聚焦窗口
+        conn.change_property32(PropMode::REPLACE, root, atoms._NET_ACTIVE_WINDOW, AtomEnum::WINDOW, &[0]).unwrap();
+        conn.sync().unwrap();
+        assert_eq!(backend.focused_pid().unwrap(), None);
+
+        for window in windows {
+            conn.destroy_window(window).unwrap();
+        }
+        conn.delete_property(root, atoms._NET_CLIENT_LIST).unwrap();
+        conn.delete_property(root, atoms._NET_ACTIVE_WINDOW).unwrap();
+        conn.sync().unwrap();
+    }
+}
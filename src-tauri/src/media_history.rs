@@ -0,0 +1,381 @@
+// 本地媒体播放记录
+//
+// 采集器每次采样后把媒体会话交给 MediaHistory，检测到曲目切换或会话消失时生成一条播放记录。
+// 记录以 JSON Lines 追加到配置目录下的 media_history.jsonl，启动时读回最近的记录，
+// 远程服务器不可达时也能查看最近播放列表。
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::config;
+use crate::media_monitor::MediaInfo;
+
+/// 内存中保留的记录数
+pub const HISTORY_CAPACITY: usize = 500;
+
+/// 两次采样间隔超过该值（例如系统休眠）时不计入播放时长
+const MAX_SAMPLE_GAP_MS: u64 = 60_000;
+
+/// 一条播放记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaHistoryEntry {
+    pub title: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub media_type: String,
+    pub source_app_id: Option<String>,
+    pub source_app_name: Option<String>,
+    pub started_at: u64,   // 开始时间（Unix 毫秒）
+    pub listened_ms: u64,  // 实际处于播放状态的时长（毫秒），暂停的时间不计入
+    #[serde(default)]
+    pub in_progress: bool, // 是否为仍在进行中的曲目
+}
+
+// 正在跟踪的曲目
+struct ActiveTrack {
+    entry: MediaHistoryEntry,
+    last_seen: u64,
+    playing: bool,
+}
+
+impl ActiveTrack {
+    fn is_same_track(&self, media: &MediaInfo) -> bool {
+        self.entry.title == media.title
+            && self.entry.artist == media.artist
+            && self.entry.album == media.album
+    }
+
+    // 把上次采样到 now 之间的播放时长计入
+    fn advance(&mut self, now: u64) {
+        let elapsed = now.saturating_sub(self.last_seen);
+        if self.playing && elapsed <= MAX_SAMPLE_GAP_MS {
+            self.entry.listened_ms += elapsed;
+        }
+        self.last_seen = now;
+    }
+}
+
+/// 媒体播放记录
+pub struct MediaHistory {
+    entries: VecDeque<MediaHistoryEntry>,  // 已完成的记录，最新的在最后
+    active: HashMap<String, ActiveTrack>,  // 每个来源应用当前的曲目
+    path: Option<PathBuf>,
+    file_lines: usize,                     // 文件中的记录数，超过容量两倍时重写
+}
+
+impl MediaHistory {
+    /// 从配置目录读取已有记录
+    pub fn load() -> Self {
+        let path = config::get_config_dir()
+            .map(|dir| dir.join("media_history.jsonl"))
+            .map_err(|e| eprintln!("无法确定播放记录文件位置: {}", e))
+            .ok();
+        Self::open(path)
+    }
+
+    // 从指定文件读取已有记录，path 为 None 时只保存在内存中
+    fn open(path: Option<PathBuf>) -> Self {
+        let mut entries = VecDeque::with_capacity(HISTORY_CAPACITY);
+        let mut file_lines = 0;
+        if let Some(content) = path.as_ref().and_then(|path| fs::read_to_string(path).ok()) {
+            for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                file_lines += 1;
+                match serde_json::from_str::<MediaHistoryEntry>(line) {
+                    Ok(entry) => {
+                        if entries.len() == HISTORY_CAPACITY {
+                            entries.pop_front();
+                        }
+                        entries.push_back(entry);
+                    }
+                    Err(e) => eprintln!("跳过无法解析的播放记录: {}", e),
+                }
+            }
+        }
+
+        Self {
+            entries,
+            active: HashMap::new(),
+            path,
+            file_lines,
+        }
+    }
+
+    /// 根据最新的媒体会话更新记录，now 为采样时间（Unix 毫秒）
+    pub fn observe(&mut self, sessions: &[MediaInfo], now: u64) {
+        let mut seen = Vec::with_capacity(sessions.len());
+
+        for media in sessions.iter().filter(|media| !media.title.is_empty()) {
+            let key = media.source_app_id.clone().unwrap_or_default();
+            if seen.contains(&key) {
+                continue;
+            }
+            seen.push(key.clone());
+
+            match self.active.get_mut(&key) {
+                Some(track) if track.is_same_track(media) => {
+                    track.advance(now);
+                    track.playing = media.playback_status == "Playing";
+                    continue;
+                }
+                _ => {}
+            }
+
+            if let Some(mut track) = self.active.remove(&key) {
+                track.advance(now);
+                self.finish(track.entry);
+            }
+
+            self.active.insert(key, ActiveTrack {
+                entry: MediaHistoryEntry {
+                    title: media.title.clone(),
+                    artist: media.artist.clone(),
+                    album: media.album.clone(),
+                    media_type: media.media_type.clone(),
+                    source_app_id: media.source_app_id.clone(),
+                    source_app_name: media.source_app_name.clone(),
+                    started_at: now,
+                    listened_ms: 0,
+                    in_progress: false,
+                },
+                last_seen: now,
+                playing: media.playback_status == "Playing",
+            });
+        }
+
+        // 会话消失（播放器关闭或停止）时结束对应的曲目
+        let gone: Vec<String> = self.active.keys().filter(|key| !seen.contains(key)).cloned().collect();
+        for key in gone {
+            if let Some(mut track) = self.active.remove(&key) {
+                track.advance(now);
+                self.finish(track.entry);
+            }
+        }
+    }
+
+    /// 结束所有正在跟踪的曲目，程序退出前调用
+    pub fn finish_all(&mut self, now: u64) {
+        let tracks: Vec<ActiveTrack> = self.active.drain().map(|(_, track)| track).collect();
+        for mut track in tracks {
+            track.advance(now);
+            self.finish(track.entry);
+        }
+    }
+
+    /// 最近的播放记录，最新的在前；进行中的曲目排在最前面
+    pub fn recent(&self, limit: usize, now: u64) -> Vec<MediaHistoryEntry> {
+        let mut in_progress: Vec<MediaHistoryEntry> = self.active
+            .values()
+            .filter(|track| track.playing || track.entry.listened_ms > 0)
+            .map(|track| {
+                let mut entry = track.entry.clone();
+                let elapsed = now.saturating_sub(track.last_seen);
+                if track.playing && elapsed <= MAX_SAMPLE_GAP_MS {
+                    entry.listened_ms += elapsed;
+                }
+                entry.in_progress = true;
+                entry
+            })
+            .collect();
+        in_progress.sort_by_key(|entry| std::cmp::Reverse(entry.started_at));
+
+        in_progress
+            .into_iter()
+            .chain(self.entries.iter().rev().cloned())
+            .take(limit)
+            .collect()
+    }
+
+    // 只记录实际播放过的曲目，一直暂停的会话不计入
+    fn finish(&mut self, entry: MediaHistoryEntry) {
+        if entry.listened_ms == 0 {
+            return;
+        }
+
+        if self.entries.len() == HISTORY_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+
+        if let Err(e) = self.persist() {
+            eprintln!("保存播放记录失败: {}", e);
+        }
+    }
+
+    // 追加最新一条记录，文件过大时只保留内存中的记录重写
+    fn persist(&mut self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if self.file_lines >= HISTORY_CAPACITY * 2 {
+            let mut content = String::new();
+            for entry in &self.entries {
+                let line = serde_json::to_string(entry)
+                    .map_err(|e| format!("序列化播放记录失败: {}", e))?;
+                content.push_str(&line);
+                content.push('\n');
+            }
+
+            // 先写入临时文件再重命名，重写中途退出不会丢掉已有的记录
+            let tmp_path = path.with_extension("jsonl.tmp");
+            let mut file = fs::File::create(&tmp_path)
+                .map_err(|e| format!("写入播放记录失败: {}", e))?;
+            file.write_all(content.as_bytes())
+                .and_then(|_| file.sync_all())
+                .map_err(|e| format!("写入播放记录失败: {}", e))?;
+            drop(file);
+            fs::rename(&tmp_path, path)
+                .map_err(|e| format!("写入播放记录失败: {}", e))?;
+            self.file_lines = self.entries.len();
+            return Ok(());
+        }
+
+        let Some(entry) = self.entries.back() else {
+            return Ok(());
+        };
+        let line = serde_json::to_string(entry)
+            .map_err(|e| format!("序列化播放记录失败: {}", e))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("打开播放记录文件失败: {}", e))?;
+        writeln!(file, "{}", line)
+            .map_err(|e| format!("写入播放记录失败: {}", e))?;
+        self.file_lines += 1;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn media(app: &str, title: &str, status: &str) -> MediaInfo {
+        serde_json::from_value(serde_json::json!({
+            "title": title,
+            "artist": "Artist",
+            "playback_status": status,
+            "media_type": "Music",
+            "source_app_id": app,
+        }))
+        .unwrap()
+    }
+
+    fn titles(entries: &[MediaHistoryEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.title.as_str()).collect()
+    }
+
+    #[test]
+    fn accumulates_listened_time_only_while_playing() {
+        let mut history = MediaHistory::open(None);
+        history.observe(&[media("player", "A", "Playing")], 0);
+        history.observe(&[media("player", "A", "Playing")], 1_000);
+        // 暂停前的一段仍按播放计入，之后不再增加
+        history.observe(&[media("player", "A", "Paused")], 3_000);
+        history.observe(&[media("player", "A", "Paused")], 8_000);
+
+        let recent = history.recent(10, 9_000);
+        assert_eq!(recent.len(), 1);
+        assert!(recent[0].in_progress);
+        assert_eq!(recent[0].listened_ms, 3_000);
+
+        // 恢复播放后继续累计，进行中的曲目计入到 now 为止
+        history.observe(&[media("player", "A", "Playing")], 10_000);
+        assert_eq!(history.recent(10, 10_500)[0].listened_ms, 3_500);
+    }
+
+    #[test]
+    fn ignores_gaps_longer_than_cutoff() {
+        let mut history = MediaHistory::open(None);
+        history.observe(&[media("player", "A", "Playing")], 0);
+        history.observe(&[media("player", "A", "Playing")], 1_000);
+        // 例如系统休眠后恢复
+        let resumed = 1_000 + MAX_SAMPLE_GAP_MS + 1;
+        history.observe(&[media("player", "A", "Playing")], resumed);
+        assert_eq!(history.recent(10, resumed)[0].listened_ms, 1_000);
+
+        history.observe(&[media("player", "A", "Playing")], resumed + 2_000);
+        assert_eq!(history.recent(10, resumed + 2_000)[0].listened_ms, 3_000);
+        assert_eq!(history.recent(10, resumed + 2_000 + MAX_SAMPLE_GAP_MS + 1)[0].listened_ms, 3_000);
+    }
+
+    #[test]
+    fn track_switch_closes_previous_entry() {
+        let mut history = MediaHistory::open(None);
+        history.observe(&[media("player", "A", "Playing")], 0);
+        history.observe(&[media("player", "A", "Playing")], 2_000);
+        history.observe(&[media("player", "B", "Playing")], 3_000);
+
+        assert_eq!(history.entries.len(), 1);
+        let finished = &history.entries[0];
+        assert_eq!(finished.title, "A");
+        assert_eq!(finished.started_at, 0);
+        assert_eq!(finished.listened_ms, 3_000);
+        assert!(!finished.in_progress);
+
+        let recent = history.recent(10, 3_000);
+        assert_eq!(titles(&recent), ["B", "A"]);
+        assert!(recent[0].in_progress);
+        assert_eq!(recent[0].started_at, 3_000);
+
+        // 其他应用的会话互不影响
+        history.observe(&[media("player", "B", "Playing"), media("other", "C", "Playing")], 4_000);
+        assert_eq!(history.entries.len(), 1);
+    }
+
+    #[test]
+    fn disappearing_session_finishes_track() {
+        let mut history = MediaHistory::open(None);
+        history.observe(&[media("player", "A", "Playing"), media("idle", "Paused all along", "Paused")], 0);
+        history.observe(&[media("player", "A", "Playing"), media("idle", "Paused all along", "Paused")], 1_000);
+        history.observe(&[], 4_000);
+
+        assert!(history.active.is_empty());
+        // 从未播放过的会话不生成记录
+        assert_eq!(titles(&history.recent(10, 4_000)), ["A"]);
+        assert_eq!(history.entries[0].listened_ms, 4_000);
+    }
+
+    #[test]
+    fn recent_is_newest_first_and_limited() {
+        let mut history = MediaHistory::open(None);
+        for (i, title) in ["A", "B", "C", "D"].iter().enumerate() {
+            let start = i as u64 * 10_000;
+            history.observe(&[media("player", title, "Playing")], start);
+            history.observe(&[media("player", title, "Playing")], start + 5_000);
+        }
+
+        // D 仍在播放，排在已完成的记录前面
+        assert_eq!(titles(&history.recent(10, 40_000)), ["D", "C", "B", "A"]);
+        assert_eq!(titles(&history.recent(2, 40_000)), ["D", "C"]);
+        assert!(history.recent(0, 40_000).is_empty());
+
+        history.finish_all(40_000);
+        assert_eq!(titles(&history.recent(3, 40_000)), ["D", "C", "B"]);
+        assert!(history.recent(3, 40_000).iter().all(|entry| !entry.in_progress));
+    }
+
+    #[test]
+    fn rewrites_file_when_it_grows_too_large() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("media_history.jsonl");
+
+        let mut history = MediaHistory::open(Some(path.clone()));
+        history.observe(&[media("player", "A", "Playing")], 0);
+        history.observe(&[media("player", "B", "Playing")], 1_000);
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 1);
+
+        // 文件行数达到上限后用内存中的记录重写
+        history.file_lines = HISTORY_CAPACITY * 2;
+        history.observe(&[media("player", "C", "Playing")], 2_000);
+        assert_eq!(history.file_lines, 2);
+        assert!(!path.with_extension("jsonl.tmp").exists());
+
+        let reloaded = MediaHistory::open(Some(path));
+        assert_eq!(reloaded.file_lines, 2);
+        assert_eq!(titles(&reloaded.recent(10, 2_000)), ["B", "A"]);
+    }
+}
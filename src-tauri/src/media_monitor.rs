@@ -1,8 +1,15 @@
 // 媒体播放状态监控模块
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use std::fs;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+#[cfg(any(target_os = "windows", target_os = "linux"))]
 use base64::{Engine as _, engine::general_purpose};
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+#[cfg(target_os = "linux")]
+mod mpris;
 
 /// 媒体信息结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,18 +19,122 @@ pub struct MediaInfo {
     pub album: Option<String>,   // 专辑
     pub duration: Option<u64>,   // 总时长（秒）
     pub position: Option<u64>,   // 当前播放位置（秒）
+    #[serde(default)]
+    pub duration_ms: Option<u64>, // 总时长（毫秒）
+    #[serde(default)]
+    pub position_ms: Option<u64>, // 当前播放位置（毫秒）
+    #[serde(default)]
+    pub position_updated_at: Option<u64>, // position_ms 对应的时间（Unix 毫秒）
+    #[serde(default = "default_playback_rate")]
+    pub playback_rate: f64,      // 播放速率，1.0 为正常速度
     pub playback_status: String, // 播放状态: Playing, Paused, Stopped
-    pub media_type: String,      // 媒体类型: Music, Video
-    pub thumbnail: Option<String>, // Base64编码的缩略图
+    pub media_type: String,      // 媒体类型: Music, Video, Podcast, Stream
+    pub thumbnail: Option<String>, // Base64编码的缩略图（inline_thumbnail 关闭时为 None）
+    #[serde(default)]
+    pub thumbnail_hash: Option<String>, // 缩略图哈希，封面或缩略图设置变化时改变
+    #[serde(default)]
+    pub thumbnail_url: Option<String>,  // 获取缩略图的相对地址
+    #[serde(default)]
+    pub source_app_id: Option<String>, // 来源应用标识（Windows 为 AppUserModelId，Linux 为 MPRIS 名称）
+    #[serde(default)]
+    pub source_app_name: Option<String>, // 来源应用名称（播放器显示名称）
+    #[serde(default)]
+    pub is_primary: bool,              // 是否为主会话（SystemInfo.media 中的会话）
+}
+
+fn default_playback_rate() -> f64 {
+    1.0
+}
+
+/// 转换为 Unix 毫秒时间戳
+pub fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl MediaInfo {
+    // 设置播放进度，秒字段由毫秒字段换算
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    fn set_timeline(&mut self, duration_ms: Option<u64>, position_ms: Option<u64>, updated_at: u64, playback_rate: f64) {
+        self.duration_ms = duration_ms;
+        self.duration = duration_ms.map(|ms| ms / 1000);
+        self.position_ms = position_ms;
+        self.position = position_ms.map(|ms| ms / 1000);
+        self.position_updated_at = position_ms.map(|_| updated_at);
+        self.playback_rate = playback_rate;
+    }
+
+    /// 按播放速率把播放位置推算到 now（Unix 毫秒），只对正在播放的会话生效，结果不超过总时长
+    pub fn extrapolate_to(&mut self, now: u64) {
+        let (Some(position_ms), Some(updated_at)) = (self.position_ms, self.position_updated_at) else {
+            return;
+        };
+        if self.playback_status != "Playing" || now <= updated_at {
+            return;
+        }
+
+        let elapsed = (now - updated_at) as f64 * self.playback_rate.max(0.0);
+        let mut position_ms = position_ms.saturating_add(elapsed as u64);
+        if let Some(duration_ms) = self.duration_ms.filter(|&ms| ms > 0) {
+            position_ms = position_ms.min(duration_ms);
+        }
+
+        self.position_ms = Some(position_ms);
+        self.position = Some(position_ms / 1000);
+        self.position_updated_at = Some(now);
+    }
+}
+
+/// 媒体类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MediaType {
+    Music,
+    Video,
+    Podcast,
+    Stream,
+}
+
+impl MediaType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MediaType::Music => "Music",
+            MediaType::Video => "Video",
+            MediaType::Podcast => "Podcast",
+            MediaType::Stream => "Stream",
+        }
+    }
+}
+
+/// 媒体类型分类规则：来源应用标识或名称包含 app（不区分大小写）时使用 media_type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaTypeRule {
+    pub app: String,
+    pub media_type: MediaType,
+}
+
+/// 压缩后缩略图的格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailFormat {
+    #[default]
+    Jpeg,
+    Png,   // 无损
+    Webp,  // 无损
 }
 
 /// 媒体配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MediaSettings {
     pub enabled: bool,               // 是否启用媒体监控
     pub send_thumbnail: bool,        // 是否发送缩略图
+    pub inline_thumbnail: bool,      // 是否在 MediaInfo 中内嵌 Base64 缩略图，关闭时只提供 thumbnail_url
     pub compress_thumbnail: bool,    // 是否压缩缩略图
     pub thumbnail_max_size_kb: u32,  // 缩略图最大大小（KB）
+    pub thumbnail_max_dimension: u32,        // 缩略图最长边（像素），0 表示不限制
+    pub thumbnail_format: ThumbnailFormat,   // 压缩后的格式
+    pub media_type_rules: Vec<MediaTypeRule>,  // 媒体类型分类规则，按顺序匹配，都不匹配时按有无艺术家/专辑判断
 }
 
 impl Default for MediaSettings {
@@ -31,95 +142,127 @@ impl Default for MediaSettings {
         Self {
             enabled: true,
             send_thumbnail: false,
+            inline_thumbnail: true,
             compress_thumbnail: true,
             thumbnail_max_size_kb: 16,
+            thumbnail_max_dimension: 0,
+            thumbnail_format: ThumbnailFormat::Jpeg,
+            media_type_rules: Vec::new(),
         }
     }
 }
 
-/// 获取媒体配置文件路径
-fn get_media_config_path() -> Result<PathBuf, String> {
-    let config_dir = tauri::api::path::config_dir()
-        .ok_or_else(|| "无法获取配置目录".to_string())?;
-    
-    let app_config_dir = config_dir.join("watchmedo");
-    
-    if !app_config_dir.exists() {
-        fs::create_dir_all(&app_config_dir)
-            .map_err(|e| format!("无法创建配置目录: {}", e))?;
-    }
-    
-    Ok(app_config_dir.join("media_settings.json"))
+/// 按分类规则确定媒体类型，没有匹配的规则时返回 None
+pub fn classify_media_type(media: &MediaInfo, rules: &[MediaTypeRule]) -> Option<MediaType> {
+    let sources: Vec<String> = [&media.source_app_id, &media.source_app_name]
+        .into_iter()
+        .flatten()
+        .map(|source| source.to_lowercase())
+        .collect();
+
+    rules
+        .iter()
+        .filter(|rule| !rule.app.trim().is_empty())
+        .find(|rule| {
+            let app = rule.app.trim().to_lowercase();
+            sources.iter().any(|source| source.contains(&app))
+        })
+        .map(|rule| rule.media_type)
 }
 
-/// 保存媒体配置
-pub fn save_media_settings(settings: &MediaSettings) -> Result<(), String> {
-    let path = get_media_config_path()?;
-    let json = serde_json::to_string_pretty(settings)
-        .map_err(|e| format!("序列化配置失败: {}", e))?;
-    
-    fs::write(&path, json)
-        .map_err(|e| format!("写入配置文件失败: {}", e))?;
-    
-    println!("媒体配置已保存到: {:?}", path);
-    Ok(())
+/// 处理后的缩略图
+#[derive(Debug)]
+pub struct Thumbnail {
+    pub hash: String,               // 原始封面字节与缩略图设置的哈希（十六进制）
+    pub content_type: &'static str,
+    pub data: Vec<u8>,
+    base64: String,
 }
 
-/// 加载媒体配置
-pub fn load_media_settings() -> MediaSettings {
-    let path = match get_media_config_path() {
-        Ok(p) => p,
-        Err(e) => {
-            eprintln!("获取配置文件路径失败: {}", e);
-            return MediaSettings::default();
-        }
-    };
-    
-    if !path.exists() {
-        println!("配置文件不存在，使用默认设置");
-        return MediaSettings::default();
-    }
-    
-    match fs::read_to_string(&path) {
-        Ok(content) => {
-            match serde_json::from_str::<MediaSettings>(&content) {
-                Ok(settings) => {
-                    println!("从文件加载媒体配置成功");
-                    settings
-                }
-                Err(e) => {
-                    eprintln!("解析配置文件失败: {}", e);
-                    MediaSettings::default()
-                }
-            }
-        }
-        Err(e) => {
-            eprintln!("读取配置文件失败: {}", e);
-            MediaSettings::default()
+/// 缩略图缓存容量（按封面数量）
+const THUMBNAIL_CACHE_CAPACITY: usize = 16;
+
+// 最近生成的缩略图，键为原始封面字节与压缩设置的哈希；压缩失败的结果也会缓存，避免每次采集重试
+static THUMBNAIL_CACHE: Mutex<VecDeque<(u64, Option<Arc<Thumbnail>>)>> = Mutex::new(VecDeque::new());
+
+/// 按哈希查找最近生成的缩略图
+pub fn find_thumbnail(hash: &str) -> Option<Arc<Thumbnail>> {
+    THUMBNAIL_CACHE.lock().unwrap()
+        .iter()
+        .filter_map(|(_, thumbnail)| thumbnail.as_ref())
+        .find(|thumbnail| thumbnail.hash == hash)
+        .cloned()
+}
+
+impl MediaInfo {
+    // 填充缩略图字段
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    fn attach_thumbnail(&mut self, thumbnail: Option<Arc<Thumbnail>>, settings: &MediaSettings) {
+        let Some(thumbnail) = thumbnail else {
+            return;
+        };
+        if settings.inline_thumbnail {
+            self.thumbnail = Some(thumbnail.base64.clone());
         }
+        self.thumbnail_url = Some(format!("/api/media/thumbnail?hash={}", thumbnail.hash));
+        self.thumbnail_hash = Some(thumbnail.hash.clone());
     }
 }
 
-/// 获取当前播放的媒体信息
+// 读取所有正在播放或暂停的媒体会话，同时返回系统当前会话的来源应用标识
 #[cfg(target_os = "windows")]
-fn get_current_media_sync() -> Option<MediaInfo> {
-    use windows::Media::Control::{
-        GlobalSystemMediaTransportControlsSessionManager,
-        GlobalSystemMediaTransportControlsSessionPlaybackStatus,
-    };
-    
+fn read_media_sessions(settings: &MediaSettings) -> (Vec<MediaInfo>, Option<String>) {
+    use windows::Media::Control::GlobalSystemMediaTransportControlsSessionManager;
+
     // 获取媒体会话管理器 (使用阻塞调用)
     let manager = match GlobalSystemMediaTransportControlsSessionManager::RequestAsync() {
         Ok(async_op) => match async_op.get() {
             Ok(mgr) => mgr,
-            Err(_) => return None,
+            Err(_) => return (Vec::new(), None),
         },
-        Err(_) => return None,
+        Err(_) => return (Vec::new(), None),
     };
-    
-    // 获取当前会话
-    let session = manager.GetCurrentSession().ok()?;
-    
+
+    // 系统认为的当前会话，用于在多个会话同时播放时选择主会话
+    let current_app_id = manager.GetCurrentSession().ok()
+        .and_then(|session| session.SourceAppUserModelId().ok())
+        .map(|id| id.to_string_lossy());
+
+    let sessions = match manager.GetSessions() {
+        Ok(sessions) => sessions,
+        Err(_) => return (Vec::new(), current_app_id),
+    };
+
+    let mut media = Vec::new();
+    for index in 0..sessions.Size().unwrap_or(0) {
+        if let Some(info) = sessions.GetAt(index).ok().and_then(|session| read_session(&session, settings)) {
+            media.push(info);
+        }
+    }
+
+    (media, current_app_id)
+}
+
+// 从 AppUserModelId 推断应用名称，例如 `Spotify.exe` -> `Spotify`，
+// `Microsoft.ZuneMusic_8wekyb3d8bbwe!Microsoft.ZuneMusic` -> `Microsoft.ZuneMusic`
+#[cfg(target_os = "windows")]
+fn app_name_from_id(app_id: &str) -> String {
+    let name = app_id.rsplit('!').next().unwrap_or(app_id);
+    let split = name.len().saturating_sub(4);
+    match (name.get(..split), name.get(split..)) {
+        (Some(stem), Some(extension)) if extension.eq_ignore_ascii_case(".exe") => stem.to_string(),
+        _ => name.to_string(),
+    }
+}
+
+// 读取单个媒体会话，已停止的会话返回 None
+#[cfg(target_os = "windows")]
+fn read_session(
+    session: &windows::Media::Control::GlobalSystemMediaTransportControlsSession,
+    settings: &MediaSettings,
+) -> Option<MediaInfo> {
+    use windows::Media::Control::GlobalSystemMediaTransportControlsSessionPlaybackStatus;
+
     // 获取播放信息
     let playback_info = session.GetPlaybackInfo().ok()?;
     let playback_status = playback_info.PlaybackStatus().ok()?;
@@ -157,11 +300,23 @@ fn get_current_media_sync() -> Option<MediaInfo> {
     // 获取时间轴信息
     let timeline = session.GetTimelineProperties().ok()?;
     
-    let duration = timeline.EndTime().ok()
-        .map(|d| d.Duration as u64 / 10_000_000); // 转换为秒
-    
-    let position = timeline.Position().ok()
-        .map(|d| d.Duration as u64 / 10_000_000);
+    // TimeSpan 以 100 纳秒为单位，转换为毫秒
+    let duration_ms = timeline.EndTime().ok()
+        .map(|d| d.Duration.max(0) as u64 / 10_000);
+
+    let position_ms = timeline.Position().ok()
+        .map(|d| d.Duration.max(0) as u64 / 10_000);
+
+    // Position 是应用最后一次上报时的位置，LastUpdatedTime 为 1601 年起的 100 纳秒数
+    const FILETIME_UNIX_EPOCH: i64 = 116_444_736_000_000_000;
+    let updated_at = timeline.LastUpdatedTime().ok()
+        .map(|t| (t.UniversalTime - FILETIME_UNIX_EPOCH).max(0) as u64 / 10_000)
+        .filter(|&ms| ms > 0)
+        .unwrap_or_else(|| unix_millis(SystemTime::now()));
+
+    let playback_rate = playback_info.PlaybackRate().ok()
+        .and_then(|rate| rate.Value().ok())
+        .unwrap_or(1.0);
     
     // 判断媒体类型（简单判断）
     let media_type = if artist.is_some() || album.is_some() {
@@ -171,27 +326,45 @@ fn get_current_media_sync() -> Option<MediaInfo> {
     }.to_string();
     
     // 获取缩略图（如果配置允许）
-    let thumbnail = get_media_thumbnail(&media_properties);
+    let thumbnail = get_media_thumbnail(&media_properties, settings);
+
+    let source_app_id = session.SourceAppUserModelId().ok()
+        .map(|id| id.to_string_lossy())
+        .filter(|id| !id.is_empty());
+    let source_app_name = source_app_id.as_deref().map(app_name_from_id);
     
-    Some(MediaInfo {
+    let mut info = MediaInfo {
         title,
         artist,
         album,
-        duration,
-        position,
+        duration: None,
+        position: None,
+        duration_ms: None,
+        position_ms: None,
+        position_updated_at: None,
+        playback_rate: 1.0,
         playback_status: status_str.to_string(),
         media_type,
-        thumbnail,
-    })
+        thumbnail: None,
+        thumbnail_hash: None,
+        thumbnail_url: None,
+        source_app_id,
+        source_app_name,
+        is_primary: false,
+    };
+    info.set_timeline(duration_ms, position_ms, updated_at, playback_rate);
+    info.attach_thumbnail(thumbnail, settings);
+
+    Some(info)
 }
 
 #[cfg(target_os = "windows")]
-fn get_media_thumbnail(media_properties: &windows::Media::Control::GlobalSystemMediaTransportControlsSessionMediaProperties) -> Option<String> {
+fn get_media_thumbnail(
+    media_properties: &windows::Media::Control::GlobalSystemMediaTransportControlsSessionMediaProperties,
+    settings: &MediaSettings,
+) -> Option<Arc<Thumbnail>> {
     use windows::Storage::Streams::DataReader;
     
-    // 加载配置
-    let settings = load_media_settings();
-    
     // 如果不发送缩略图，直接返回
     if !settings.send_thumbnail {
         return None;
@@ -222,62 +395,178 @@ fn get_media_thumbnail(media_properties: &windows::Media::Control::GlobalSystemM
     let mut buffer = vec![0u8; size as usize];
     reader.ReadBytes(&mut buffer).ok()?;
     
+    prepare_thumbnail(buffer, settings)
+}
+
+/// 按媒体配置压缩缩略图，同一封面和设置只压缩一次
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn prepare_thumbnail(buffer: Vec<u8>, settings: &MediaSettings) -> Option<Arc<Thumbnail>> {
+    // 如果不发送缩略图，直接返回
+    if !settings.send_thumbnail {
+        return None;
+    }
+
+    let mut hasher = DefaultHasher::new();
+    buffer.hash(&mut hasher);
+    settings.compress_thumbnail.hash(&mut hasher);
+    settings.thumbnail_max_size_kb.hash(&mut hasher);
+    settings.thumbnail_max_dimension.hash(&mut hasher);
+    settings.thumbnail_format.hash(&mut hasher);
+    let key = hasher.finish();
+
+    {
+        let mut cache = THUMBNAIL_CACHE.lock().unwrap();
+        if let Some(index) = cache.iter().position(|(k, _)| *k == key) {
+            // 移到队尾，淘汰时保留最近使用的封面
+            let entry = cache.remove(index)?;
+            let thumbnail = entry.1.clone();
+            cache.push_back(entry);
+            return thumbnail;
+        }
+    }
+
+    let thumbnail = build_thumbnail(&buffer, settings).map(|data| {
+        let content_type = match image::guess_format(&data) {
+            Ok(image::ImageFormat::Jpeg) => "image/jpeg",
+            Ok(image::ImageFormat::Png) => "image/png",
+            Ok(image::ImageFormat::WebP) => "image/webp",
+            Ok(image::ImageFormat::Gif) => "image/gif",
+            Ok(image::ImageFormat::Bmp) => "image/bmp",
+            _ => "application/octet-stream",
+        };
+        Arc::new(Thumbnail {
+            hash: format!("{:016x}", key),
+            content_type,
+            base64: general_purpose::STANDARD.encode(&data),
+            data,
+        })
+    });
+
+    let mut cache = THUMBNAIL_CACHE.lock().unwrap();
+    if cache.len() >= THUMBNAIL_CACHE_CAPACITY {
+        cache.pop_front();
+    }
+    cache.push_back((key, thumbnail.clone()));
+
+    thumbnail
+}
+
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn build_thumbnail(buffer: &[u8], settings: &MediaSettings) -> Option<Vec<u8>> {
     // 如果需要压缩
     if settings.compress_thumbnail {
-        match compress_image(&buffer, settings.thumbnail_max_size_kb) {
-            Ok(compressed) => Some(general_purpose::STANDARD.encode(&compressed)),
+        match compress_image(buffer, settings) {
+            Ok(compressed) => Some(compressed),
             Err(e) => {
                 eprintln!("压缩图片失败: {}", e);
                 // 如果压缩失败，检查原图大小
                 if buffer.len() <= (settings.thumbnail_max_size_kb as usize * 1024) {
-                    Some(general_purpose::STANDARD.encode(&buffer))
+                    Some(buffer.to_vec())
                 } else {
                     None
                 }
             }
         }
     } else {
-        Some(general_purpose::STANDARD.encode(&buffer))
+        Some(buffer.to_vec())
     }
 }
 
-/// 压缩图片到指定大小
-fn compress_image(data: &[u8], max_size_kb: u32) -> Result<Vec<u8>, String> {
-    use image::GenericImageView;
+/// JPEG 质量搜索范围
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+const JPEG_QUALITY_RANGE: (u8, u8) = (20, 95);
+
+// 按格式编码图片
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn encode_image(img: &image::DynamicImage, format: ThumbnailFormat, quality: u8) -> Result<Vec<u8>, String> {
     use std::io::Cursor;
+
+    let mut buffer = Vec::new();
+    let mut cursor = Cursor::new(&mut buffer);
+    let result = match format {
+        // JPEG 不支持透明通道
+        ThumbnailFormat::Jpeg => image::DynamicImage::ImageRgb8(img.to_rgb8())
+            .write_with_encoder(image::codecs::jpeg::JpegEncoder::new_with_quality(&mut cursor, quality)),
+        ThumbnailFormat::Png => img.write_with_encoder(image::codecs::png::PngEncoder::new_with_quality(
+            &mut cursor,
+            image::codecs::png::CompressionType::Best,
+            image::codecs::png::FilterType::Adaptive,
+        )),
+        ThumbnailFormat::Webp => img.write_with_encoder(image::codecs::webp::WebPEncoder::new_lossless(&mut cursor)),
+    };
+    result.map_err(|e| format!("编码图片失败: {}", e))?;
+
+    Ok(buffer)
+}
+
+// 二分查找不超过预算的最高 JPEG 质量
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn encode_jpeg_within(img: &image::DynamicImage, budget: usize) -> Result<Option<Vec<u8>>, String> {
+    let (mut low, mut high) = JPEG_QUALITY_RANGE;
+    let mut best = None;
+
+    while low <= high {
+        let quality = low + (high - low) / 2;
+        let buffer = encode_image(img, ThumbnailFormat::Jpeg, quality)?;
+        if buffer.len() <= budget {
+            best = Some(buffer);
+            low = quality + 1;
+        } else {
+            high = quality - 1;
+        }
+    }
+
+    Ok(best)
+}
+
+/// 按媒体配置压缩图片：先缩放到最大边长，再按格式压缩到指定大小；
+/// JPEG 在每个尺寸下二分查找最高质量，PNG / WebP 为无损格式，只通过缩小尺寸满足大小限制
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn compress_image(data: &[u8], settings: &MediaSettings) -> Result<Vec<u8>, String> {
+    use image::GenericImageView;
     
     // 加载图片
-    let img = image::load_from_memory(data)
+    let mut img = image::load_from_memory(data)
         .map_err(|e| format!("加载图片失败: {}", e))?;
+
+    // 限制最长边，resize 会保持宽高比
+    let max_dimension = settings.thumbnail_max_dimension;
+    if max_dimension > 0 {
+        let (width, height) = img.dimensions();
+        if width > max_dimension || height > max_dimension {
+            img = img.resize(max_dimension, max_dimension, image::imageops::FilterType::Lanczos3);
+        }
+    }
     
     // 获取原始尺寸
     let (width, height) = img.dimensions();
+    let budget = settings.thumbnail_max_size_kb as usize * 1024;
     
     // 尝试不同的缩放比例
     let scales = [1.0, 0.8, 0.6, 0.5, 0.4, 0.3, 0.25, 0.2];
     
     for &scale in &scales {
-        let new_width = (width as f32 * scale) as u32;
-        let new_height = (height as f32 * scale) as u32;
+        let new_width = ((width as f32 * scale) as u32).max(1);
+        let new_height = ((height as f32 * scale) as u32).max(1);
         
         // 调整大小
-        let resized = img.resize(new_width, new_height, image::imageops::FilterType::Lanczos3);
-        
-        // 尝试不同的质量
-        let qualities = [85, 75, 65, 55, 45, 35, 25];
-        
-        for &quality in &qualities {
-            let mut buffer = Vec::new();
-            let mut cursor = Cursor::new(&mut buffer);
-            
-            // 使用JPEG格式和指定质量
-            let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut cursor, quality);
-            resized.write_with_encoder(encoder)
-                .map_err(|e| format!("编码图片失败: {}", e))?;
-            
-            // 检查大小
-            if buffer.len() <= (max_size_kb as usize * 1024) {
-                return Ok(buffer);
+        let resized = if scale == 1.0 {
+            img.clone()
+        } else {
+            img.resize(new_width, new_height, image::imageops::FilterType::Lanczos3)
+        };
+
+        match settings.thumbnail_format {
+            ThumbnailFormat::Jpeg => {
+                if let Some(buffer) = encode_jpeg_within(&resized, budget)? {
+                    return Ok(buffer);
+                }
+            }
+            format => {
+                let buffer = encode_image(&resized, format, 0)?;
+                if buffer.len() <= budget {
+                    return Ok(buffer);
+                }
             }
         }
     }
@@ -285,33 +574,439 @@ fn compress_image(data: &[u8], max_size_kb: u32) -> Result<Vec<u8>, String> {
     Err("无法将图片压缩到指定大小".to_string())
 }
 
-#[cfg(not(target_os = "windows"))]
-fn get_current_media_sync() -> Option<MediaInfo> {
+#[cfg(target_os = "linux")]
+fn read_media_sessions(settings: &MediaSettings) -> (Vec<MediaInfo>, Option<String>) {
+    (mpris::get_media_sessions(settings), None)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn read_media_sessions(_settings: &MediaSettings) -> (Vec<MediaInfo>, Option<String>) {
     // 其他平台暂不支持
-    None
+    (Vec::new(), None)
 }
 
-// 异步包装函数
-pub async fn get_current_media() -> Option<MediaInfo> {
-    // 在阻塞线程池中运行，避免 Send 问题
-    tokio::task::spawn_blocking(|| {
-        get_current_media_sync()
-    }).await.ok().flatten()
+/// 获取所有正在播放或暂停的媒体会话，主会话排在第一位；媒体监控关闭时返回空列表
+pub fn get_media_sessions_sync(settings: &MediaSettings) -> Vec<MediaInfo> {
+    if !settings.enabled {
+        return Vec::new();
+    }
+
+    let (mut sessions, current_app_id) = read_media_sessions(settings);
+    for media in &mut sessions {
+        if let Some(media_type) = classify_media_type(media, &settings.media_type_rules) {
+            media.media_type = media_type.as_str().to_string();
+        }
+    }
+
+    select_primary(&mut sessions, current_app_id.as_deref());
+    sessions
 }
 
-// Tauri Commands
-#[tauri::command]
-pub fn get_media_settings() -> MediaSettings {
-    load_media_settings()
+/// 选出主会话并移到第一位：优先正在播放的会话，其中优先系统当前会话；
+/// 没有正在播放的会话时使用系统当前会话，否则使用第一个会话
+pub fn select_primary(sessions: &mut [MediaInfo], current_app_id: Option<&str>) {
+    let is_current = |media: &MediaInfo| current_app_id.is_some() && media.source_app_id.as_deref() == current_app_id;
+    let is_playing = |media: &MediaInfo| media.playback_status == "Playing";
+
+    let primary = sessions.iter().position(|m| is_playing(m) && is_current(m))
+        .or_else(|| sessions.iter().position(is_playing))
+        .or_else(|| sessions.iter().position(is_current));
+
+    if let Some(index) = primary {
+        sessions[..=index].rotate_right(1);
+    }
+    for (index, media) in sessions.iter_mut().enumerate() {
+        media.is_primary = index == 0;
+    }
 }
 
-#[tauri::command]
-pub fn set_media_settings(settings: MediaSettings) -> Result<(), String> {
-    save_media_settings(&settings)
+/// 获取主媒体会话
+pub fn get_current_media_sync(settings: &MediaSettings) -> Option<MediaInfo> {
+    get_media_sessions_sync(settings).into_iter().next()
 }
 
-#[tauri::command]
-pub async fn get_current_media_info() -> Option<MediaInfo> {
-    get_current_media().await
+// 异步包装函数
+pub async fn get_current_media(settings: MediaSettings) -> Option<MediaInfo> {
+    // 在阻塞线程池中运行，避免 Send 问题
+    tokio::task::spawn_blocking(move || {
+        get_current_media_sync(&settings)
+    }).await.ok().flatten()
 }
 
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn media(title: &str, status: &str) -> MediaInfo {
+        MediaInfo {
+            title: title.to_string(),
+            artist: None,
+            album: None,
+            duration: None,
+            position: None,
+            duration_ms: None,
+            position_ms: None,
+            position_updated_at: None,
+            playback_rate: 1.0,
+            playback_status: status.to_string(),
+            media_type: "Music".to_string(),
+            thumbnail: None,
+            thumbnail_hash: None,
+            thumbnail_url: None,
+            source_app_id: None,
+            source_app_name: None,
+            is_primary: false,
+        }
+    }
+
+    fn at_position(status: &str, position_ms: u64, duration_ms: Option<u64>, playback_rate: f64) -> MediaInfo {
+        MediaInfo {
+            position_ms: Some(position_ms),
+            position: Some(position_ms / 1000),
+            position_updated_at: Some(1_000_000),
+            duration_ms,
+            duration: duration_ms.map(|ms| ms / 1000),
+            playback_rate,
+            ..media("Song", status)
+        }
+    }
+
+    #[test]
+    fn extrapolate_advances_playing_position() {
+        let mut info = at_position("Playing", 10_000, Some(180_000), 1.0);
+        info.extrapolate_to(1_002_500);
+        assert_eq!(info.position_ms, Some(12_500));
+        assert_eq!(info.position, Some(12));
+        assert_eq!(info.position_updated_at, Some(1_002_500));
+
+        // now 早于记录时间时不变
+        let mut info = at_position("Playing", 10_000, Some(180_000), 1.0);
+        info.extrapolate_to(999_000);
+        assert_eq!(info.position_ms, Some(10_000));
+        assert_eq!(info.position_updated_at, Some(1_000_000));
+    }
+
+    #[test]
+    fn extrapolate_keeps_paused_position() {
+        for status in ["Paused", "Stopped"] {
+            let mut info = at_position(status, 10_000, Some(180_000), 1.0);
+            info.extrapolate_to(1_060_000);
+            assert_eq!(info.position_ms, Some(10_000));
+            assert_eq!(info.position_updated_at, Some(1_000_000));
+        }
+    }
+
+    #[test]
+    fn extrapolate_uses_playback_rate() {
+        let mut info = at_position("Playing", 10_000, Some(180_000), 2.0);
+        info.extrapolate_to(1_003_000);
+        assert_eq!(info.position_ms, Some(16_000));
+
+        let mut info = at_position("Playing", 10_000, Some(180_000), 0.5);
+        info.extrapolate_to(1_003_000);
+        assert_eq!(info.position_ms, Some(11_500));
+
+        // 负速率按 0 处理，不会倒退
+        let mut info = at_position("Playing", 10_000, Some(180_000), -1.0);
+        info.extrapolate_to(1_003_000);
+        assert_eq!(info.position_ms, Some(10_000));
+    }
+
+    #[test]
+    fn extrapolate_clamps_to_duration() {
+        let mut info = at_position("Playing", 179_000, Some(180_000), 1.0);
+        info.extrapolate_to(1_010_000);
+        assert_eq!(info.position_ms, Some(180_000));
+        assert_eq!(info.position, Some(180));
+    }
+
+    #[test]
+    fn extrapolate_without_duration_is_unbounded() {
+        // 直播等未知时长（或报告为 0）的会话不限制
+        for duration_ms in [None, Some(0)] {
+            let mut info = at_position("Playing", 179_000, duration_ms, 1.0);
+            info.extrapolate_to(1_010_000);
+            assert_eq!(info.position_ms, Some(189_000));
+        }
+
+        // 没有播放位置时不推算
+        let mut info = media("Song", "Playing");
+        info.extrapolate_to(1_010_000);
+        assert_eq!(info.position_ms, None);
+        assert_eq!(info.position_updated_at, None);
+    }
+
+    fn session(app: &str, status: &str) -> MediaInfo {
+        MediaInfo {
+            source_app_id: Some(app.to_string()),
+            ..media(app, status)
+        }
+    }
+
+    fn order(sessions: &[MediaInfo]) -> Vec<&str> {
+        sessions.iter().map(|media| media.title.as_str()).collect()
+    }
+
+    fn assert_single_primary(sessions: &[MediaInfo]) {
+        assert_eq!(sessions.iter().filter(|media| media.is_primary).count(), 1);
+        assert!(sessions[0].is_primary);
+    }
+
+    #[test]
+    fn primary_prefers_playing_current_session() {
+        let mut sessions = vec![
+            session("a", "Playing"),
+            session("b", "Paused"),
+            session("c", "Playing"),
+        ];
+        select_primary(&mut sessions, Some("c"));
+        // 其余会话保持原来的顺序
+        assert_eq!(order(&sessions), ["c", "a", "b"]);
+        assert_single_primary(&sessions);
+    }
+
+    #[test]
+    fn primary_prefers_playing_over_paused_current() {
+        let mut sessions = vec![
+            session("a", "Paused"),
+            session("b", "Stopped"),
+            session("c", "Playing"),
+        ];
+        select_primary(&mut sessions, Some("a"));
+        assert_eq!(order(&sessions), ["c", "a", "b"]);
+        assert_single_primary(&sessions);
+    }
+
+    #[test]
+    fn primary_tie_break_order() {
+        // 多个正在播放的会话都不是当前会话时取第一个
+        let mut sessions = vec![
+            session("a", "Paused"),
+            session("b", "Playing"),
+            session("c", "Playing"),
+        ];
+        select_primary(&mut sessions, Some("x"));
+        assert_eq!(order(&sessions), ["b", "a", "c"]);
+        assert_single_primary(&sessions);
+
+        // 没有正在播放的会话时使用当前会话
+        let mut sessions = vec![session("a", "Paused"), session("b", "Paused")];
+        select_primary(&mut sessions, Some("b"));
+        assert_eq!(order(&sessions), ["b", "a"]);
+        assert_single_primary(&sessions);
+
+        // 都不满足时使用第一个会话
+        let mut sessions = vec![session("a", "Paused"), session("b", "Stopped")];
+        select_primary(&mut sessions, None);
+        assert_eq!(order(&sessions), ["a", "b"]);
+        assert_single_primary(&sessions);
+    }
+
+    #[test]
+    fn primary_is_reset_on_every_selection() {
+        let mut sessions = vec![session("a", "Playing"), session("b", "Paused")];
+        select_primary(&mut sessions, None);
+        assert_eq!(order(&sessions), ["a", "b"]);
+
+        // 上一次的主会话暂停后，标记转移到新的主会话
+        sessions[0].playback_status = "Paused".to_string();
+        sessions[1].playback_status = "Playing".to_string();
+        select_primary(&mut sessions, None);
+        assert_eq!(order(&sessions), ["b", "a"]);
+        assert_single_primary(&sessions);
+
+        // 会话中没有来源标识时不会被当作当前会话
+        let mut sessions = vec![media("none", "Paused"), session("b", "Paused")];
+        select_primary(&mut sessions, None);
+        assert_eq!(order(&sessions), ["none", "b"]);
+        assert_single_primary(&sessions);
+
+        let mut sessions: Vec<MediaInfo> = Vec::new();
+        select_primary(&mut sessions, Some("a"));
+        assert!(sessions.is_empty());
+    }
+
+    fn rule(app: &str, media_type: MediaType) -> MediaTypeRule {
+        MediaTypeRule { app: app.to_string(), media_type }
+    }
+
+    fn from_app(app_id: Option<&str>, app_name: Option<&str>) -> MediaInfo {
+        MediaInfo {
+            source_app_id: app_id.map(str::to_string),
+            source_app_name: app_name.map(str::to_string),
+            ..media("Episode 12", "Playing")
+        }
+    }
+
+    #[test]
+    fn classify_uses_first_matching_rule() {
+        let rules = [
+            rule("spotify", MediaType::Music),
+            rule("podcast", MediaType::Podcast),
+            rule("spot", MediaType::Video),
+        ];
+        let media = from_app(Some("org.mpris.MediaPlayer2.spotify"), None);
+        assert_eq!(classify_media_type(&media, &rules), Some(MediaType::Music));
+
+        // 调换顺序后较宽泛的规则先匹配
+        let rules = [rule("spot", MediaType::Video), rule("spotify", MediaType::Music)];
+        assert_eq!(classify_media_type(&media, &rules), Some(MediaType::Video));
+    }
+
+    #[test]
+    fn classify_matches_app_id_or_name_but_not_title() {
+        let rules = [rule("pocket casts", MediaType::Podcast), rule("vlc", MediaType::Video)];
+
+        assert_eq!(classify_media_type(&from_app(None, Some("Pocket Casts")), &rules), Some(MediaType::Podcast));
+        assert_eq!(classify_media_type(&from_app(Some("org.videolan.vlc"), Some("Media")), &rules), Some(MediaType::Video));
+
+        // 规则只匹配来源应用，不匹配标题
+        let titled = MediaInfo {
+            title: "vlc tutorial".to_string(),
+            ..from_app(Some("firefox"), Some("Firefox"))
+        };
+        assert_eq!(classify_media_type(&titled, &rules), None);
+    }
+
+    #[test]
+    fn classify_is_case_insensitive() {
+        let rules = [rule("  YouTube ", MediaType::Video)];
+        assert_eq!(classify_media_type(&from_app(Some("youtube.com"), None), &rules), Some(MediaType::Video));
+        assert_eq!(classify_media_type(&from_app(None, Some("YOUTUBE Music")), &rules), Some(MediaType::Video));
+    }
+
+    #[test]
+    fn classify_returns_none_without_match() {
+        let media = from_app(Some("spotify"), Some("Spotify"));
+        assert_eq!(classify_media_type(&media, &[]), None);
+        assert_eq!(classify_media_type(&media, &[rule("vlc", MediaType::Video)]), None);
+        // 空规则不会匹配所有应用
+        assert_eq!(classify_media_type(&media, &[rule("  ", MediaType::Stream)]), None);
+        // 没有来源信息的会话不匹配任何规则
+        assert_eq!(classify_media_type(&from_app(None, None), &[rule("e", MediaType::Stream)]), None);
+    }
+
+    // 噪声图片几乎无法压缩，便于构造超出预算的情况
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    fn noise_image(width: u32, height: u32) -> image::DynamicImage {
+        let mut seed: u32 = 0x9e37_79b9;
+        image::DynamicImage::ImageRgb8(image::RgbImage::from_fn(width, height, |_, _| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            let [r, g, b, _] = seed.to_le_bytes();
+            image::Rgb([r, g, b])
+        }))
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    fn png_bytes(img: &image::DynamicImage) -> Vec<u8> {
+        encode_image(img, ThumbnailFormat::Png, 0).unwrap()
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    fn compress_settings(max_size_kb: u32, format: ThumbnailFormat) -> MediaSettings {
+        MediaSettings {
+            thumbnail_max_size_kb: max_size_kb,
+            thumbnail_format: format,
+            ..MediaSettings::default()
+        }
+    }
+
+    // 按 JPEG 最低质量编码原尺寸图片时的大小
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    fn min_quality_jpeg_len(img: &image::DynamicImage) -> usize {
+        encode_image(img, ThumbnailFormat::Jpeg, JPEG_QUALITY_RANGE.0).unwrap().len()
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    #[test]
+    fn jpeg_search_fits_budget() {
+        let img = noise_image(128, 128);
+        let lowest = min_quality_jpeg_len(&img);
+        let highest = encode_image(&img, ThumbnailFormat::Jpeg, JPEG_QUALITY_RANGE.1).unwrap();
+
+        let budget = (lowest + highest.len()) / 2;
+        let buffer = encode_jpeg_within(&img, budget).unwrap().unwrap();
+        assert!(buffer.len() <= budget, "{} > {}", buffer.len(), budget);
+        assert!(buffer.len() >= lowest);
+        assert_eq!(image::guess_format(&buffer).unwrap(), image::ImageFormat::Jpeg);
+
+        // 预算足够时使用最高质量
+        assert_eq!(encode_jpeg_within(&img, highest.len()).unwrap().unwrap(), highest);
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    #[test]
+    fn jpeg_search_returns_none_for_unreachable_budget() {
+        let img = noise_image(128, 128);
+        let lowest = min_quality_jpeg_len(&img);
+        assert_eq!(encode_jpeg_within(&img, lowest - 1).unwrap(), None);
+        assert_eq!(encode_jpeg_within(&img, 0).unwrap(), None);
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    #[test]
+    fn compress_falls_back_to_smaller_size() {
+        let img = noise_image(256, 192);
+        // 原尺寸以最低质量编码也超出预算
+        let max_size_kb = ((min_quality_jpeg_len(&img) - 1) / 1024) as u32;
+        assert!(max_size_kb > 0);
+
+        let buffer = compress_image(&png_bytes(&img), &compress_settings(max_size_kb, ThumbnailFormat::Jpeg)).unwrap();
+        assert!(buffer.len() <= max_size_kb as usize * 1024);
+
+        let compressed = image::load_from_memory(&buffer).unwrap();
+        assert!(compressed.width() < 256 && compressed.height() < 192);
+        // 缩放保持宽高比
+        assert_eq!(compressed.width() * 3, compressed.height() * 4);
+
+        // 无损格式同样只能通过缩小尺寸满足限制
+        let png_kb = (png_bytes(&img).len() / 1024 / 2) as u32;
+        let buffer = compress_image(&png_bytes(&img), &compress_settings(png_kb, ThumbnailFormat::Png)).unwrap();
+        assert!(buffer.len() <= png_kb as usize * 1024);
+        assert!(image::load_from_memory(&buffer).unwrap().width() < 256);
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    #[test]
+    fn compress_fails_when_no_scale_fits() {
+        let img = noise_image(64, 64);
+        let settings = compress_settings(0, ThumbnailFormat::Jpeg);
+        assert!(compress_image(&png_bytes(&img), &settings).is_err());
+        assert!(compress_image(b"not an image", &settings).is_err());
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    #[test]
+    fn compress_keeps_at_least_one_pixel() {
+        // 宽度按比例缩小后为 0，应保留 1 像素
+        let img = noise_image(1, 4096);
+        let max_size_kb = ((min_quality_jpeg_len(&img) - 1) / 1024) as u32;
+        assert!(max_size_kb > 0);
+
+        let buffer = compress_image(&png_bytes(&img), &compress_settings(max_size_kb, ThumbnailFormat::Jpeg)).unwrap();
+        assert!(buffer.len() <= max_size_kb as usize * 1024);
+        let compressed = image::load_from_memory(&buffer).unwrap();
+        assert_eq!(compressed.width(), 1);
+        assert!(compressed.height() < 4096);
+
+        // 所有比例都不满足时不会因 0 像素的尺寸出错
+        let tiny = noise_image(2, 2);
+        assert!(compress_image(&png_bytes(&tiny), &compress_settings(0, ThumbnailFormat::Png)).is_err());
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    #[test]
+    fn compress_limits_longest_side() {
+        let img = noise_image(300, 150);
+        let settings = MediaSettings {
+            thumbnail_max_dimension: 100,
+            ..compress_settings(1024, ThumbnailFormat::Png)
+        };
+        let compressed = image::load_from_memory(&compress_image(&png_bytes(&img), &settings).unwrap()).unwrap();
+        assert_eq!((compressed.width(), compressed.height()), (100, 50));
+    }
+}
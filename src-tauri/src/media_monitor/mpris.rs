@@ -0,0 +1,513 @@
+// Linux 媒体检测：通过会话总线上的 MPRIS 接口读取播放器状态
+//
+// 枚举所有 `org.mpris.MediaPlayer2.*` 名称，读取 `/org/mpris/MediaPlayer2` 上
+// `org.mpris.MediaPlayer2.Player` 接口的全部属性，每个正在播放或暂停的播放器是一个会话。
+// 总线连接在两次采集之间复用，出错时丢弃并在下一次采集时重连。
+//
+// 封面地址为 http(s) 时在后台下载，下载完成前的采样没有缩略图；结果（包括失败）按地址缓存，
+// 同一封面只下载一次。
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+use zbus::blocking::{fdo::{DBusProxy, PropertiesProxy}, Connection};
+use zbus::names::InterfaceName;
+use zbus::zvariant::{OwnedValue, Value};
+use super::{prepare_thumbnail, unix_millis, MediaInfo, MediaSettings};
+
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+const ROOT_INTERFACE: &str = "org.mpris.MediaPlayer2";
+const PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+static CONNECTION: Mutex<Option<Connection>> = Mutex::new(None);
+
+/// 网络封面下载超时
+const ART_FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+/// 网络封面大小上限（字节），超过时不使用
+const ART_MAX_BYTES: usize = 2 * 1024 * 1024;
+/// 网络封面缓存容量（按地址数量）
+const ART_CACHE_CAPACITY: usize = 8;
+
+// 网络封面的下载状态
+enum RemoteArt {
+    Pending,
+    Ready(Option<Vec<u8>>),
+}
+
+// 最近使用的网络封面，键为封面地址
+static REMOTE_ART: Mutex<VecDeque<(String, RemoteArt)>> = Mutex::new(VecDeque::new());
+
+// 按 D-Bus 类型读取整数，不同播放器对时长使用 x / t / i 等不同类型
+fn as_i64(value: &Value) -> Option<i64> {
+    match value {
+        Value::I64(v) => Some(*v),
+        Value::U64(v) => i64::try_from(*v).ok(),
+        Value::I32(v) => Some(*v as i64),
+        Value::U32(v) => Some(*v as i64),
+        Value::F64(v) => Some(*v as i64),
+        Value::Value(inner) => as_i64(inner),
+        _ => None,
+    }
+}
+
+fn as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::F64(v) => Some(*v),
+        Value::Value(inner) => as_f64(inner),
+        other => as_i64(other).map(|v| v as f64),
+    }
+}
+
+fn as_string(value: &Value) -> Option<String> {
+    match value {
+        Value::Str(s) => Some(s.to_string()),
+        Value::ObjectPath(path) => Some(path.to_string()),
+        Value::Value(inner) => as_string(inner),
+        _ => None,
+    }
+}
+
+// xesam:artist 是字符串数组，多位艺术家用逗号连接
+fn as_string_list(value: &Value) -> Option<String> {
+    match value {
+        Value::Array(array) => {
+            let items: Vec<String> = array.iter().filter_map(as_string).filter(|s| !s.is_empty()).collect();
+            (!items.is_empty()).then(|| items.join(", "))
+        }
+        Value::Value(inner) => as_string_list(inner),
+        other => as_string(other),
+    }
+}
+
+// 微秒转换为毫秒
+fn micros_to_millis(micros: i64) -> Option<u64> {
+    u64::try_from(micros).ok().map(|micros| micros / 1_000)
+}
+
+// 读取封面：file:// 直接读取，http(s) 地址从缓存读取，未缓存时开始在后台下载
+fn read_art(url: &str) -> Option<Vec<u8>> {
+    if let Some(path) = url.strip_prefix("file://") {
+        return std::fs::read(percent_decode(path)).ok();
+    }
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return remote_art(url);
+    }
+    None
+}
+
+fn remote_art(url: &str) -> Option<Vec<u8>> {
+    let mut cache = REMOTE_ART.lock().unwrap();
+    if let Some(index) = cache.iter().position(|(cached, _)| cached == url) {
+        // 移到队尾，淘汰时保留最近使用的封面
+        let entry = cache.remove(index)?;
+        let art = match &entry.1 {
+            RemoteArt::Ready(art) => art.clone(),
+            RemoteArt::Pending => None,
+        };
+        cache.push_back(entry);
+        return art;
+    }
+
+    // 采集在 spawn_blocking 中运行，可以取得 tokio 运行时；没有运行时时不下载
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return None;
+    };
+
+    if cache.len() >= ART_CACHE_CAPACITY {
+        cache.pop_front();
+    }
+    cache.push_back((url.to_string(), RemoteArt::Pending));
+
+    let url = url.to_string();
+    runtime.spawn(async move {
+        let art = match fetch_art(&url, ART_MAX_BYTES).await {
+            Ok(art) => Some(art),
+            Err(e) => {
+                eprintln!("下载封面 {} 失败: {}", url, e);
+                None
+            }
+        };
+
+        // 下载期间可能已被淘汰，此时丢弃结果
+        if let Some(entry) = REMOTE_ART.lock().unwrap().iter_mut().find(|(cached, _)| *cached == url) {
+            entry.1 = RemoteArt::Ready(art);
+        }
+    });
+
+    None
+}
+
+// 下载网络封面，超过 max_bytes 时放弃
+async fn fetch_art(url: &str, max_bytes: usize) -> Result<Vec<u8>, String> {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    let client = CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(ART_FETCH_TIMEOUT)
+            .build()
+            .unwrap_or_default()
+    });
+
+    let mut response = client.get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| e.to_string())?;
+
+    if response.content_length().is_some_and(|length| length > max_bytes as u64) {
+        return Err(format!("封面超过 {} 字节", max_bytes));
+    }
+
+    // 没有 Content-Length 或与实际不符时边读边检查
+    let mut art = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        if art.len() + chunk.len() > max_bytes {
+            return Err(format!("封面超过 {} 字节", max_bytes));
+        }
+        art.extend_from_slice(&chunk);
+    }
+
+    Ok(art)
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn read_player(connection: &Connection, name: &str, settings: &MediaSettings) -> zbus::Result<Option<MediaInfo>> {
+    let properties = PropertiesProxy::builder(connection)
+        .destination(name)?
+        .path(MPRIS_PATH)?
+        .cache_properties(zbus::CacheProperties::No)
+        .build()?;
+    let all: HashMap<String, OwnedValue> = properties.get_all(Some(InterfaceName::from_static_str_unchecked(PLAYER_INTERFACE)).into())?;
+
+    let playback_status = all.get("PlaybackStatus").and_then(|v| as_string(v)).unwrap_or_default();
+    if playback_status != "Playing" && playback_status != "Paused" {
+        return Ok(None);
+    }
+
+    let metadata: HashMap<String, Value> = match all.get("Metadata").map(|v| &**v) {
+        Some(Value::Dict(dict)) => dict
+            .iter()
+            .filter_map(|(key, value)| Some((as_string(key)?, value.try_clone().ok()?)))
+            .collect(),
+        _ => HashMap::new(),
+    };
+
+    let title = metadata.get("xesam:title").and_then(as_string).unwrap_or_default();
+    let artist = metadata.get("xesam:artist").and_then(as_string_list);
+    let album = metadata.get("xesam:album").and_then(as_string).filter(|s| !s.is_empty());
+    let duration_ms = metadata.get("mpris:length").and_then(as_i64).and_then(micros_to_millis);
+    // Position 在读取时由播放器实时计算，采样时间即当前时间
+    let position_ms = all.get("Position").and_then(|v| as_i64(v)).and_then(micros_to_millis);
+    let sampled_at = unix_millis(SystemTime::now());
+    let playback_rate = all.get("Rate").and_then(|v| as_f64(v)).unwrap_or(1.0);
+
+    // 判断媒体类型（简单判断）
+    let media_type = if artist.is_some() || album.is_some() {
+        "Music"
+    } else {
+        "Video"
+    }.to_string();
+
+    // 播放器显示名称，例如 "Spotify"、"Mozilla Firefox"
+    let source_app_name = properties
+        .get(InterfaceName::from_static_str_unchecked(ROOT_INTERFACE), "Identity")
+        .ok()
+        .and_then(|identity| as_string(&identity))
+        .filter(|identity| !identity.is_empty());
+
+    let thumbnail = metadata.get("mpris:artUrl")
+        .filter(|_| settings.send_thumbnail)
+        .and_then(as_string)
+        .and_then(|url| read_art(&url))
+        .and_then(|art| prepare_thumbnail(art, settings));
+
+    let mut info = MediaInfo {
+        title,
+        artist,
+        album,
+        duration: None,
+        position: None,
+        duration_ms: None,
+        position_ms: None,
+        position_updated_at: None,
+        playback_rate: 1.0,
+        playback_status,
+        media_type,
+        thumbnail: None,
+        thumbnail_hash: None,
+        thumbnail_url: None,
+        source_app_id: name.strip_prefix(MPRIS_PREFIX).map(str::to_string),
+        source_app_name,
+        is_primary: false,
+    };
+    info.set_timeline(duration_ms, position_ms, sampled_at, playback_rate);
+    info.attach_thumbnail(thumbnail, settings);
+
+    Ok(Some(info))
+}
+
+fn media_sessions(connection: &Connection, settings: &MediaSettings) -> zbus::Result<Vec<MediaInfo>> {
+    let mut names: Vec<String> = DBusProxy::new(connection)?
+        .list_names()?
+        .into_iter()
+        .map(|name| name.to_string())
+        .filter(|name| name.starts_with(MPRIS_PREFIX))
+        .collect();
+    names.sort();
+
+    let mut sessions = Vec::new();
+    for name in names {
+        // 单个播放器无响应时跳过，不影响其他播放器
+        match read_player(connection, &name, settings) {
+            Ok(Some(player)) => sessions.push(player),
+            Ok(None) => {}
+            Err(e) => eprintln!("读取播放器 {} 失败: {}", name, e),
+        }
+    }
+
+    Ok(sessions)
+}
+
+pub fn get_media_sessions(settings: &MediaSettings) -> Vec<MediaInfo> {
+    let mut connection = CONNECTION.lock().unwrap();
+    if connection.is_none() {
+        // 没有会话总线（例如服务器上）时每次采集都会失败，不输出日志
+        *connection = Connection::session().ok();
+    }
+
+    let Some(connected) = connection.as_ref() else {
+        return Vec::new();
+    };
+
+    match media_sessions(connected, settings) {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            eprintln!("读取 MPRIS 媒体信息失败: {}", e);
+            *connection = None;
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_artist_lists() {
+        assert_eq!(as_string_list(&Value::from(vec!["Artist A", "", "Artist B"])).as_deref(), Some("Artist A, Artist B"));
+        assert_eq!(as_string_list(&Value::from(Vec::<&str>::new())), None);
+        assert_eq!(as_string_list(&Value::from(vec![""])), None);
+        // 包在 variant 里的数组，以及不规范地直接返回字符串的播放器
+        assert_eq!(as_string_list(&Value::Value(Box::new(Value::from(vec!["歌手"])))).as_deref(), Some("歌手"));
+        assert_eq!(as_string_list(&Value::from("Solo")).as_deref(), Some("Solo"));
+        assert_eq!(as_string_list(&Value::from(3i32)), None);
+    }
+
+    #[test]
+    fn converts_micros_to_millis() {
+        assert_eq!(micros_to_millis(0), Some(0));
+        assert_eq!(micros_to_millis(215_000_999), Some(215_000));
+        assert_eq!(micros_to_millis(-1), None);
+    }
+
+    #[test]
+    fn decodes_percent_escapes() {
+        assert_eq!(percent_decode("/tmp/fake%20art.png"), "/tmp/fake art.png");
+        assert_eq!(percent_decode("/tmp/%E5%B0%81%E9%9D%A2.jpg"), "/tmp/封面.jpg");
+        // 无效或不完整的转义原样保留
+        assert_eq!(percent_decode("/tmp/100%zz"), "/tmp/100%zz");
+        assert_eq!(percent_decode("/tmp/a%2"), "/tmp/a%2");
+        assert_eq!(percent_decode("/tmp/a%"), "/tmp/a%");
+    }
+
+    #[test]
+    fn reads_local_art() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("封面 art.png");
+        std::fs::write(&path, b"art").unwrap();
+
+        let url = format!("file://{}", path.display())
+            .replace(' ', "%20")
+            .replace("封面", "%E5%B0%81%E9%9D%A2");
+        assert_eq!(read_art(&url), Some(b"art".to_vec()));
+        assert_eq!(read_art(&format!("file://{}", dir.path().join("missing.png").display())), None);
+        assert_eq!(read_art("ftp://example.com/art.png"), None);
+        // 没有 tokio 运行时时不下载网络封面
+        assert_eq!(read_art("https://example.com/art.png"), None);
+    }
+
+    // 在本机随机端口上提供封面，每个连接返回一次 body，返回地址和请求计数
+    async fn serve_art(body: Vec<u8>, content_length: bool) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+        use std::sync::atomic::Ordering;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let requests = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = requests.clone();
+
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut head = [0u8; 1024];
+                let _ = stream.read(&mut head).await;
+
+                let mut response = b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\nConnection: close\r\n".to_vec();
+                if content_length {
+                    response.extend_from_slice(format!("Content-Length: {}\r\n", body.len()).as_bytes());
+                }
+                response.extend_from_slice(b"\r\n");
+                response.extend_from_slice(&body);
+                let _ = stream.write_all(&response).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+
+        (format!("http://{}/art.png", address), requests)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn downloads_remote_art_once() {
+        use std::sync::atomic::Ordering;
+
+        let (url, requests) = serve_art(b"remote art".to_vec(), true).await;
+
+        // 与采集相同，在 spawn_blocking 中读取；首次读取开始下载，完成后从缓存返回
+        let art = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let url = url.clone();
+                if let Some(art) = tokio::task::spawn_blocking(move || read_art(&url)).await.unwrap() {
+                    return art;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("下载封面超时");
+
+        assert_eq!(art, b"remote art");
+        assert_eq!(read_art(&url).as_deref(), Some(&b"remote art"[..]));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_remote_art() {
+        let (url, _) = serve_art(vec![0u8; 4096], true).await;
+        assert_eq!(fetch_art(&url, 4096).await.unwrap().len(), 4096);
+        assert!(fetch_art(&url, 4095).await.is_err());
+
+        // 没有 Content-Length 时读取过程中检查大小
+        let (url, _) = serve_art(vec![0u8; 4096], false).await;
+        assert!(fetch_art(&url, 1024).await.is_err());
+        assert_eq!(fetch_art(&url, 4096).await.unwrap().len(), 4096);
+
+        assert!(fetch_art("http://127.0.0.1:1/art.png", 4096).await.is_err());
+    }
+
+    struct TestPlayer;
+
+    #[zbus::interface(name = "org.mpris.MediaPlayer2.Player")]
+    impl TestPlayer {
+        #[zbus(property)]
+        fn playback_status(&self) -> String {
+            "Playing".to_string()
+        }
+
+        #[zbus(property)]
+        fn position(&self) -> i64 {
+            42_500_000
+        }
+
+        #[zbus(property)]
+        fn rate(&self) -> f64 {
+            1.5
+        }
+
+        #[zbus(property)]
+        fn metadata(&self) -> HashMap<String, OwnedValue> {
+            HashMap::from([
+                ("xesam:title".to_string(), OwnedValue::try_from(Value::from("测试歌曲")).unwrap()),
+                ("xesam:artist".to_string(), OwnedValue::try_from(Value::from(vec!["Artist A", "Artist B"])).unwrap()),
+                ("mpris:length".to_string(), OwnedValue::try_from(Value::from(215_000_000i64)).unwrap()),
+            ])
+        }
+    }
+
+    struct TestRoot;
+
+    #[zbus::interface(name = "org.mpris.MediaPlayer2")]
+    impl TestRoot {
+        #[zbus(property)]
+        fn identity(&self) -> String {
+            "Test Player".to_string()
+        }
+    }
+
+    // 测试结束时关闭私有总线
+    struct Daemon(std::process::Child);
+
+    impl Drop for Daemon {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    #[test]
+    #[ignore = "需要 dbus-daemon：cargo test --no-default-features -- --ignored mpris"]
+    fn reads_player_from_private_session_bus() {
+        use std::io::BufRead;
+        use zbus::blocking::connection::Builder;
+
+        let mut child = std::process::Command::new("dbus-daemon")
+            .args(["--session", "--print-address", "--nofork"])
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .expect("启动 dbus-daemon 失败");
+        let stdout = child.stdout.take().unwrap();
+        let _daemon = Daemon(child);
+
+        let mut address = String::new();
+        std::io::BufReader::new(stdout).read_line(&mut address).unwrap();
+        let address = address.trim();
+
+        let _player = Builder::address(address).unwrap()
+            .name("org.mpris.MediaPlayer2.test").unwrap()
+            .serve_at(MPRIS_PATH, TestRoot).unwrap()
+            .serve_at(MPRIS_PATH, TestPlayer).unwrap()
+            .build()
+            .unwrap();
+        let connection = Builder::address(address).unwrap().build().unwrap();
+
+        let sessions = media_sessions(&connection, &MediaSettings::default()).unwrap();
+        assert_eq!(sessions.len(), 1);
+
+        let session = &sessions[0];
+        assert_eq!(session.title, "测试歌曲");
+        assert_eq!(session.artist.as_deref(), Some("Artist A, Artist B"));
+        assert_eq!(session.playback_status, "Playing");
+        assert_eq!(session.duration_ms, Some(215_000));
+        assert_eq!(session.position_ms, Some(42_500));
+        assert_eq!(session.playback_rate, 1.5);
+        assert_eq!(session.source_app_id.as_deref(), Some("test"));
+        assert_eq!(session.source_app_name.as_deref(), Some("Test Player"));
+    }
+}
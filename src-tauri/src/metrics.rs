@@ -0,0 +1,212 @@
+// Prometheus 文本格式指标
+//
+// 指标从经过共享设置和令牌范围过滤后的 SystemInfo 生成，和 /api/system 看到的数据一致。
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use crate::system_info::SystemInfo;
+
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+// 转义标签值中的反斜杠、双引号和换行
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+struct MetricsWriter {
+    out: String,
+}
+
+impl MetricsWriter {
+    fn family(&mut self, name: &str, kind: &str, help: &str) {
+        let _ = writeln!(self.out, "# HELP {} {}", name, help);
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind);
+    }
+
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(key, value)| format!("{}=\"{}\"", key, escape_label(value)))
+                .collect();
+            let _ = write!(self.out, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.out, " {}", value);
+    }
+}
+
+// 同一可执行文件的进程合计
+#[derive(Default)]
+struct ProcessTotals {
+    count: usize,
+    cpu_usage: f64,
+    memory: u64,
+    focused: bool,
+}
+
+/// 将系统信息渲染为 Prometheus 文本格式
+pub fn render(info: &SystemInfo) -> String {
+    let mut w = MetricsWriter { out: String::new() };
+
+    if let Some(uptime) = info.uptime {
+        w.family("watchmedo_uptime_seconds", "gauge", "System uptime in seconds.");
+        w.sample("watchmedo_uptime_seconds", &[], uptime as f64);
+    }
+
+    if let Some(cpu_usage) = &info.cpu_usage {
+        w.family("watchmedo_cpu_usage_percent", "gauge", "CPU usage per core in percent.");
+        for (core, usage) in cpu_usage.iter().enumerate() {
+            w.sample("watchmedo_cpu_usage_percent", &[("core", &core.to_string())], *usage as f64);
+        }
+    }
+
+    if let Some(memory) = &info.memory_usage {
+        w.family("watchmedo_memory_total_bytes", "gauge", "Total memory in bytes.");
+        w.sample("watchmedo_memory_total_bytes", &[], memory.total as f64);
+        w.family("watchmedo_memory_used_bytes", "gauge", "Used memory in bytes.");
+        w.sample("watchmedo_memory_used_bytes", &[], memory.used as f64);
+    }
+
+    if let Some(disks) = &info.disks {
+        w.family("watchmedo_disk_total_bytes", "gauge", "Disk size in bytes.");
+        for disk in disks {
+            w.sample("watchmedo_disk_total_bytes", &[("name", &disk.name), ("mount_point", &disk.mount_point)], disk.total_space as f64);
+        }
+        w.family("watchmedo_disk_available_bytes", "gauge", "Available disk space in bytes.");
+        for disk in disks {
+            w.sample("watchmedo_disk_available_bytes", &[("name", &disk.name), ("mount_point", &disk.mount_point)], disk.available_space as f64);
+        }
+    }
+
+    if let Some(networks) = &info.network {
+        w.family("watchmedo_network_received_bytes_total", "counter", "Bytes received per interface.");
+        for network in networks {
+            w.sample("watchmedo_network_received_bytes_total", &[("interface", &network.name)], network.received as f64);
+        }
+        w.family("watchmedo_network_transmitted_bytes_total", "counter", "Bytes transmitted per interface.");
+        for network in networks {
+            w.sample("watchmedo_network_transmitted_bytes_total", &[("interface", &network.name)], network.transmitted as f64);
+        }
+    }
+
+    if let Some(battery) = &info.battery {
+        w.family("watchmedo_battery_percent", "gauge", "Battery charge in percent.");
+        w.sample("watchmedo_battery_percent", &[], battery.percentage as f64);
+        w.family("watchmedo_battery_charging", "gauge", "Whether the battery is charging (1) or not (0).");
+        w.sample("watchmedo_battery_charging", &[], if battery.is_charging { 1.0 } else { 0.0 });
+    }
+
+    if let Some(processes) = &info.processes {
+        // 按可执行文件名汇总，pid 作为标签时每次进程重启都会产生新的时间序列
+        let mut by_name: BTreeMap<&str, ProcessTotals> = BTreeMap::new();
+        for process in processes {
+            let totals = by_name.entry(process.executable_name.as_str()).or_default();
+            totals.count += 1;
+            totals.cpu_usage += process.cpu_usage as f64;
+            totals.memory += process.memory;
+            totals.focused |= process.is_focused;
+        }
+
+        w.family("watchmedo_process_count", "gauge", "Number of listed processes per executable.");
+        for (name, totals) in &by_name {
+            w.sample("watchmedo_process_count", &[("name", name)], totals.count as f64);
+        }
+        w.family("watchmedo_process_cpu_usage_percent", "gauge", "CPU usage of listed processes per executable in percent.");
+        for (name, totals) in &by_name {
+            w.sample("watchmedo_process_cpu_usage_percent", &[("name", name)], totals.cpu_usage);
+        }
+        w.family("watchmedo_process_memory_bytes", "gauge", "Memory usage of listed processes per executable in bytes.");
+        for (name, totals) in &by_name {
+            w.sample("watchmedo_process_memory_bytes", &[("name", name)], totals.memory as f64);
+        }
+        w.family("watchmedo_process_focused", "gauge", "Whether a process of the executable owns the focused window (1) or not (0).");
+        for (name, totals) in &by_name {
+            w.sample("watchmedo_process_focused", &[("name", name)], if totals.focused { 1.0 } else { 0.0 });
+        }
+    }
+
+    // 未共享媒体信息时不输出，避免与“没有播放”混淆
+    if info.media.is_some() || info.media_sessions.is_some() {
+        w.family("watchmedo_media_playing", "gauge", "Whether media is currently playing (1) or not (0).");
+        let playing = info.media.as_ref().is_some_and(|media| media.playback_status == "Playing");
+        w.sample("watchmedo_media_playing", &[], if playing { 1.0 } else { 0.0 });
+    }
+
+    w.out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system_info::ProcessInfo;
+
+    fn empty_info() -> SystemInfo {
+        SystemInfo {
+            computer_name: None,
+            uptime: None,
+            cpu_usage: None,
+            memory_usage: None,
+            processes: None,
+            disks: None,
+            network: None,
+            battery: None,
+            media: None,
+            media_sessions: None,
+        }
+    }
+
+    fn process(pid: u32, name: &str, cpu_usage: f32, memory: u64, is_focused: bool) -> ProcessInfo {
+        ProcessInfo {
+            memory,
+            is_focused,
+            window_title: String::new(),
+            executable_name: name.to_string(),
+            pid,
+            cpu_usage,
+        }
+    }
+
+    #[test]
+    fn aggregates_processes_by_name() {
+        let info = SystemInfo {
+            processes: Some(vec![
+                process(10, "chrome", 1.5, 100, false),
+                process(11, "chrome", 2.5, 200, true),
+                process(20, "say \"hi\"", 0.5, 50, false),
+            ]),
+            ..empty_info()
+        };
+        let out = render(&info);
+
+        assert!(!out.contains("pid="), "{}", out);
+        assert!(out.contains("watchmedo_process_count{name=\"chrome\"} 2\n"), "{}", out);
+        assert!(out.contains("watchmedo_process_cpu_usage_percent{name=\"chrome\"} 4\n"), "{}", out);
+        assert!(out.contains("watchmedo_process_memory_bytes{name=\"chrome\"} 300\n"), "{}", out);
+        assert!(out.contains("watchmedo_process_focused{name=\"chrome\"} 1\n"), "{}", out);
+        assert!(out.contains("watchmedo_process_focused{name=\"say \\\"hi\\\"\"} 0\n"), "{}", out);
+        assert_eq!(out.matches("# TYPE watchmedo_process_memory_bytes gauge").count(), 1);
+    }
+
+    #[test]
+    fn media_playing_only_when_media_is_shared() {
+        // 未共享媒体（或令牌无权查看）时不输出该指标
+        let out = render(&empty_info());
+        assert!(!out.contains("watchmedo_media_playing"), "{}", out);
+
+        // 已共享但没有媒体会话时输出 0
+        let info = SystemInfo { media_sessions: Some(Vec::new()), ..empty_info() };
+        assert!(render(&info).contains("watchmedo_media_playing 0\n"));
+
+        let media: crate::media_monitor::MediaInfo = serde_json::from_value(serde_json::json!({
+            "title": "Song",
+            "playback_status": "Playing",
+            "media_type": "Music",
+        }))
+        .unwrap();
+        let info = SystemInfo { media: Some(media.clone()), media_sessions: Some(vec![media]), ..empty_info() };
+        assert!(render(&info).contains("watchmedo_media_playing 1\n"));
+    }
+}
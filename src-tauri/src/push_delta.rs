@@ -0,0 +1,227 @@
+// 远程推送的增量模式
+//
+// 每隔一段时间发送一次完整快照（mode = "full"），其余时候只发送与上一条采集数据相比
+// 发生变化的顶层分项（mode = "delta"）。队列按顺序发送，每条增量以队列中的前一条数据为基准。
+//
+// 前一条数据可能到不了服务器：被服务器拒绝（400 / 413 / 422）或队列已满时被丢弃。
+// 此时推送任务调用 request_full，下一条数据发送完整快照；已在队列中的增量仍会发送，
+// 服务器按已知状态合并并通过递增的序号 seq 发现不连续，在响应中返回 "resync": true，
+// 服务器丢失了状态时同样如此，下一条数据也会是完整快照。
+use std::time::Duration;
+use serde_json::{Map, Value};
+use tokio::time::Instant;
+
+// 采集数据中不属于 SystemInfo 分项的字段，每条数据都会携带
+const META_FIELDS: [&str; 3] = ["timestamp", "seq", "mode"];
+
+// 比较时忽略的字段：进程的 CPU / 内存占用和媒体播放位置每次采集都会变化，
+// 只有它们变化时不单独发送，在下一次完整快照或该分项因其他原因变化时更新
+const VOLATILE_PROCESS_FIELDS: [&str; 2] = ["cpu_usage", "memory"];
+pub(crate) const VOLATILE_MEDIA_FIELDS: [&str; 3] = ["position", "position_ms", "position_updated_at"];
+
+// 去掉 items 中每个对象的 fields 字段
+pub(crate) fn strip_fields(value: &Value, fields: &[&str]) -> Value {
+    let strip = |item: &Value| match item {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .filter(|(key, _)| !fields.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        ),
+        other => other.clone(),
+    };
+
+    match value {
+        Value::Array(items) => Value::Array(items.iter().map(strip).collect()),
+        other => strip(other),
+    }
+}
+
+// 用于判断分项是否变化的形式；进程按 CPU 占用率排序，顺序变化不算变化
+fn comparable(key: &str, value: &Value) -> Value {
+    match key {
+        "processes" => {
+            let mut processes = strip_fields(value, &VOLATILE_PROCESS_FIELDS);
+            if let Value::Array(items) = &mut processes {
+                items.sort_by_key(|item| item["pid"].as_u64());
+            }
+            processes
+        }
+        "media" | "media_sessions" => strip_fields(value, &VOLATILE_MEDIA_FIELDS),
+        _ => value.clone(),
+    }
+}
+
+pub struct DeltaEncoder {
+    seq: u64,
+    last: Option<Map<String, Value>>,  // 上一条数据各分项的比较形式
+    last_full: Option<Instant>,
+}
+
+impl DeltaEncoder {
+    pub fn new() -> Self {
+        Self {
+            seq: 0,
+            last: None,
+            last_full: None,
+        }
+    }
+
+    /// 服务器要求重新同步或有数据被丢弃，下一条数据发送完整快照
+    pub fn request_full(&mut self) {
+        self.last = None;
+    }
+
+    /// 把完整的采集数据编码为完整快照或增量，full_interval 为完整快照的间隔
+    pub fn encode(&mut self, mut sample: Map<String, Value>, full_interval: Duration) -> Map<String, Value> {
+        self.seq += 1;
+        let now = Instant::now();
+
+        let current: Map<String, Value> = sample
+            .iter()
+            .filter(|(key, _)| !META_FIELDS.contains(&key.as_str()))
+            .map(|(key, value)| (key.clone(), comparable(key, value)))
+            .collect();
+
+        let full_due = self.last_full.is_none_or(|last_full| now.duration_since(last_full) >= full_interval);
+        let Some(previous) = self.last.take().filter(|_| !full_due) else {
+            self.last = Some(current);
+            self.last_full = Some(now);
+            sample.insert("seq".to_string(), self.seq.into());
+            sample.insert("mode".to_string(), "full".into());
+            return sample;
+        };
+
+        sample.retain(|key, _| {
+            META_FIELDS.contains(&key.as_str()) || previous.get(key) != current.get(key)
+        });
+        self.last = Some(current);
+        sample.insert("seq".to_string(), self.seq.into());
+        sample.insert("mode".to_string(), "delta".into());
+        sample
+    }
+}
+
+impl Default for DeltaEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    fn sample(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    fn base_sample() -> Map<String, Value> {
+        sample(json!({
+            "timestamp": 1000,
+            "computer_name": "pc",
+            "memory": { "total": 16, "used": 8 },
+            "processes": [
+                { "pid": 1, "name": "a", "cpu_usage": 1.0, "memory": 100 },
+                { "pid": 2, "name": "b", "cpu_usage": 5.0, "memory": 200 },
+            ],
+            "media": { "title": "歌曲", "position": 10, "position_ms": 10_000, "position_updated_at": 1000 },
+        }))
+    }
+
+    fn keys(map: &Map<String, Value>) -> Vec<&str> {
+        let mut keys: Vec<&str> = map.keys().map(String::as_str).collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn first_sample_is_full() {
+        let mut encoder = DeltaEncoder::new();
+        let encoded = encoder.encode(base_sample(), HOUR);
+
+        assert_eq!(encoded["mode"], "full");
+        assert_eq!(encoded["seq"], 1);
+        for key in base_sample().keys() {
+            assert_eq!(encoded[key], base_sample()[key], "{}", key);
+        }
+    }
+
+    #[test]
+    fn omits_unchanged_sections() {
+        let mut encoder = DeltaEncoder::new();
+        encoder.encode(base_sample(), HOUR);
+
+        let mut next = base_sample();
+        next.insert("timestamp".to_string(), json!(2000));
+        let encoded = encoder.encode(next.clone(), HOUR);
+        assert_eq!(encoded["mode"], "delta");
+        assert_eq!(encoded["seq"], 2);
+        assert_eq!(keys(&encoded), ["mode", "seq", "timestamp"]);
+        assert_eq!(encoded["timestamp"], 2000);
+
+        next.insert("memory".to_string(), json!({ "total": 16, "used": 9 }));
+        let encoded = encoder.encode(next, HOUR);
+        assert_eq!(keys(&encoded), ["memory", "mode", "seq", "timestamp"]);
+        assert_eq!(encoded["memory"]["used"], 9);
+    }
+
+    #[test]
+    fn ignores_volatile_fields() {
+        let mut encoder = DeltaEncoder::new();
+        encoder.encode(base_sample(), HOUR);
+
+        // 进程 CPU / 内存占用和顺序变化、媒体播放位置变化都不算变化
+        let mut next = base_sample();
+        next.insert("processes".to_string(), json!([
+            { "pid": 2, "name": "b", "cpu_usage": 0.5, "memory": 300 },
+            { "pid": 1, "name": "a", "cpu_usage": 9.0, "memory": 100 },
+        ]));
+        next.insert("media".to_string(), json!({ "title": "歌曲", "position": 15, "position_ms": 15_000, "position_updated_at": 6000 }));
+        let encoded = encoder.encode(next.clone(), HOUR);
+        assert_eq!(keys(&encoded), ["mode", "seq", "timestamp"]);
+
+        // 其他字段变化时发送整个分项，包括最新的易变字段
+        next.insert("media".to_string(), json!({ "title": "下一首", "position": 0, "position_ms": 0, "position_updated_at": 7000 }));
+        next.insert("processes".to_string(), json!([
+            { "pid": 1, "name": "a", "cpu_usage": 9.0, "memory": 100 },
+            { "pid": 3, "name": "c", "cpu_usage": 2.0, "memory": 50 },
+        ]));
+        let encoded = encoder.encode(next, HOUR);
+        assert_eq!(keys(&encoded), ["media", "mode", "processes", "seq", "timestamp"]);
+        assert_eq!(encoded["media"]["title"], "下一首");
+        assert_eq!(encoded["processes"][1]["pid"], 3);
+    }
+
+    #[test]
+    fn full_interval_forces_full_sample() {
+        let mut encoder = DeltaEncoder::new();
+        assert_eq!(encoder.encode(base_sample(), Duration::ZERO)["mode"], "full");
+
+        let encoded = encoder.encode(base_sample(), Duration::ZERO);
+        assert_eq!(encoded["mode"], "full");
+        assert_eq!(encoded["seq"], 2);
+        assert_eq!(encoded["computer_name"], "pc");
+    }
+
+    #[test]
+    fn request_full_sends_next_sample_in_full() {
+        let mut encoder = DeltaEncoder::new();
+        encoder.encode(base_sample(), HOUR);
+        assert_eq!(encoder.encode(base_sample(), HOUR)["mode"], "delta");
+
+        encoder.request_full();
+        let encoded = encoder.encode(base_sample(), HOUR);
+        assert_eq!(encoded["mode"], "full");
+        assert_eq!(encoded["seq"], 3);
+        assert_eq!(encoded["processes"], base_sample()["processes"]);
+
+        // 之后恢复为增量，序号继续递增
+        let encoded = encoder.encode(base_sample(), HOUR);
+        assert_eq!(encoded["mode"], "delta");
+        assert_eq!(encoded["seq"], 4);
+    }
+}
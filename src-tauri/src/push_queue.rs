@@ -0,0 +1,279 @@
+// 远程推送的离线队列
+//
+// 等待发送的采集数据保存在配置目录下的 JSON Lines 文件中（每个推送目标一个文件），每行一条，
+// 推送失败时服务器恢复后按原顺序重新发送，批量模式下也用于攒够一批再上传。
+// 采集数据自带采集时间，重发不会改变记录的时间。队列有容量上限，超出时丢弃最旧的数据。
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use crate::config;
+
+pub struct PushQueue {
+    entries: VecDeque<String>,
+    capacity: usize,
+    path: Option<PathBuf>,
+    on_disk: usize,  // 队首开始已写入文件的条数
+    stale: bool,     // 文件中包含已发送或已丢弃的数据，需要重写
+}
+
+impl PushQueue {
+    /// 读取配置目录下 file_name 中上次未发送完的数据
+    pub fn load(file_name: &str, capacity: usize) -> Self {
+        let path = config::get_config_dir()
+            .map(|dir| dir.join(file_name))
+            .map_err(|e| eprintln!("无法确定推送队列文件位置: {}", e))
+            .ok();
+        Self::open(path, capacity)
+    }
+
+    /// 删除配置目录下 file_name 中未发送的数据，推送目标被删除时调用
+    pub fn remove(file_name: &str) -> Result<(), String> {
+        let path = config::get_config_dir()?.join(file_name);
+        for path in [path.with_extension("jsonl.tmp"), path] {
+            if path.exists() {
+                fs::remove_file(&path)
+                    .map_err(|e| format!("删除推送队列文件失败: {}", e))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn open(path: Option<PathBuf>, capacity: usize) -> Self {
+        let entries: VecDeque<String> = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|content| {
+                content
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut queue = Self {
+            on_disk: entries.len(),
+            entries,
+            capacity: capacity.max(1),
+            path,
+            stale: false,
+        };
+        queue.trim();
+        queue
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 队列中数据的总字节数
+    pub fn total_bytes(&self) -> usize {
+        self.entries.iter().map(String::len).sum()
+    }
+
+    /// 从队首取出一批数据：最多 max_count 条、总大小不超过 max_bytes，但至少一条
+    pub fn peek_batch(&self, max_count: usize, max_bytes: usize) -> Vec<&str> {
+        let mut batch = Vec::new();
+        let mut bytes = 0;
+        for body in self.entries.iter().take(max_count.max(1)) {
+            if !batch.is_empty() && bytes + body.len() > max_bytes {
+                break;
+            }
+            bytes += body.len();
+            batch.push(body.as_str());
+        }
+        batch
+    }
+
+    /// 加入一条采集数据（JSON 单行），返回是否因队列已满丢弃了最旧的数据
+    pub fn push(&mut self, body: String) -> bool {
+        self.entries.push_back(body);
+        self.trim()
+    }
+
+    /// 移除最早的 count 条数据（发送成功或被服务器拒绝）
+    pub fn pop_front(&mut self, count: usize) {
+        for _ in 0..count {
+            self.pop();
+        }
+    }
+
+    fn pop(&mut self) {
+        if self.entries.pop_front().is_some() && self.on_disk > 0 {
+            self.on_disk -= 1;
+            self.stale = true;
+        }
+    }
+
+    // 超出容量时一次丢弃十分之一的旧数据，避免每次加入都重写文件；返回是否丢弃了数据
+    fn trim(&mut self) -> bool {
+        if self.entries.len() <= self.capacity {
+            return false;
+        }
+
+        let excess = self.entries.len() - self.capacity;
+        let drop = excess.max(self.capacity / 10).min(self.entries.len());
+        eprintln!("推送队列已满，丢弃最早的 {} 条数据", drop);
+        for _ in 0..drop {
+            self.pop();
+        }
+        true
+    }
+
+    /// 把内存中的队列同步到文件：没有删除时只追加新数据，队列为空时删除文件
+    pub fn save(&mut self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        if self.entries.is_empty() {
+            if self.stale && path.exists() {
+                fs::remove_file(path)
+                    .map_err(|e| format!("删除推送队列文件失败: {}", e))?;
+            }
+            self.stale = false;
+            self.on_disk = 0;
+            return Ok(());
+        }
+
+        if !self.stale && self.on_disk == self.entries.len() {
+            return Ok(());
+        }
+
+        let start = if self.stale { 0 } else { self.on_disk };
+        let mut content = String::new();
+        for body in self.entries.iter().skip(start) {
+            content.push_str(body);
+            content.push('\n');
+        }
+
+        if self.stale {
+            // 先写入临时文件再重命名，重写过程中退出不会丢失队列
+            let tmp_path = path.with_extension("jsonl.tmp");
+            let mut file = fs::File::create(&tmp_path)
+                .map_err(|e| format!("写入推送队列文件失败: {}", e))?;
+            file.write_all(content.as_bytes())
+                .and_then(|_| file.sync_all())
+                .map_err(|e| format!("写入推送队列文件失败: {}", e))?;
+            drop(file);
+
+            fs::rename(&tmp_path, path)
+                .map_err(|e| format!("写入推送队列文件失败: {}", e))?;
+        } else {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| format!("打开推送队列文件失败: {}", e))?;
+            file.write_all(content.as_bytes())
+                .map_err(|e| format!("写入推送队列文件失败: {}", e))?;
+        }
+
+        self.on_disk = self.entries.len();
+        self.stale = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(queue: &PushQueue) -> Vec<&str> {
+        queue.entries.iter().map(String::as_str).collect()
+    }
+
+    fn file_lines(path: &std::path::Path) -> Vec<String> {
+        fs::read_to_string(path).unwrap().lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn trims_a_tenth_when_full() {
+        let mut queue = PushQueue::open(None, 20);
+        for i in 0..20 {
+            assert!(!queue.push(i.to_string()));
+        }
+        assert_eq!(queue.len(), 20);
+
+        // 超出 1 条时丢弃 capacity / 10 = 2 条最旧的数据
+        assert!(queue.push("20".to_string()));
+        assert_eq!(queue.len(), 19);
+        assert_eq!(queue.entries.front().map(String::as_str), Some("2"));
+        assert_eq!(queue.entries.back().map(String::as_str), Some("20"));
+
+        // 容量小于 10 时每次只丢弃超出的部分
+        let mut small = PushQueue::open(None, 3);
+        for i in 0..4 {
+            small.push(i.to_string());
+        }
+        assert_eq!(entries(&small), ["1", "2", "3"]);
+    }
+
+    #[test]
+    fn load_trims_to_capacity() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("push_queue.jsonl");
+        let content: String = (0..30).map(|i| format!("{}\n", i)).collect();
+        fs::write(&path, content + "\n").unwrap();
+
+        let queue = PushQueue::open(Some(path), 20);
+        assert_eq!(queue.len(), 20);
+        assert_eq!(queue.entries.front().map(String::as_str), Some("10"));
+    }
+
+    #[test]
+    fn peek_batch_respects_limits() {
+        let mut queue = PushQueue::open(None, 100);
+        for body in ["aaaa", "bbbb", "cccc", "dddd"] {
+            queue.push(body.to_string());
+        }
+        assert_eq!(queue.total_bytes(), 16);
+
+        assert_eq!(queue.peek_batch(10, 1024), ["aaaa", "bbbb", "cccc", "dddd"]);
+        assert_eq!(queue.peek_batch(2, 1024), ["aaaa", "bbbb"]);
+        assert_eq!(queue.peek_batch(10, 8), ["aaaa", "bbbb"]);
+        assert_eq!(queue.peek_batch(10, 11), ["aaaa", "bbbb"]);
+        // 第一条超出大小限制或条数为 0 时仍返回一条
+        assert_eq!(queue.peek_batch(10, 2), ["aaaa"]);
+        assert_eq!(queue.peek_batch(0, 1024), ["aaaa"]);
+
+        assert!(PushQueue::open(None, 100).peek_batch(10, 1024).is_empty());
+    }
+
+    #[test]
+    fn appends_new_entries_and_rewrites_after_removal() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("push_queue.jsonl");
+
+        let mut queue = PushQueue::open(Some(path.clone()), 100);
+        queue.push("a".to_string());
+        queue.push("b".to_string());
+        queue.save().unwrap();
+        assert_eq!(file_lines(&path), ["a", "b"]);
+
+        // 没有删除时只追加：先改动文件中已有的行，追加后改动仍在
+        fs::write(&path, "x\nb\n").unwrap();
+        queue.push("c".to_string());
+        queue.save().unwrap();
+        assert_eq!(file_lines(&path), ["x", "b", "c"]);
+
+        // 删除后重写整个文件，不留下临时文件
+        queue.pop_front(1);
+        queue.save().unwrap();
+        assert_eq!(file_lines(&path), ["b", "c"]);
+        assert!(!path.with_extension("jsonl.tmp").exists());
+
+        let reloaded = PushQueue::open(Some(path.clone()), 100);
+        assert_eq!(entries(&reloaded), ["b", "c"]);
+
+        // 队列清空后删除文件
+        queue.pop_front(2);
+        queue.save().unwrap();
+        assert!(!path.exists());
+    }
+}
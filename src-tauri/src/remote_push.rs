@@ -0,0 +1,716 @@
+// 远程推送模块
+//
+// 每个推送目标（config::PushTarget）由独立的任务推送，各自有 URL、token、间隔、离线队列和可选的共享设置，
+// 例如向内部服务器推送完整数据，同时向公开的状态页只推送媒体和聚焦的应用。
+//
+// 每次采样的数据先进入队列（见 push_queue），单条模式下立即发送，批量模式下攒够一批
+// 或到达上传间隔时一次发送。发送失败时按指数退避加随机抖动重试，服务器恢复后按原顺序补发。
+//
+// 推送内容带有格式版本和类型：单条推送在采集数据的字段之外加上 version / type，
+// 旧版服务器忽略这两个字段即可照常处理；批量推送把多条采集数据放在 samples 数组中。
+//
+// 请求体可按设置用 gzip / zstd 压缩并带上 Content-Encoding，服务器返回 415 时
+// 立即用未压缩的 JSON 重发，并在推送任务的剩余时间内不再压缩。
+//
+// 开启增量推送时采集数据按 push_delta 编码后再进入队列，服务器在响应中返回
+// "resync": true 或队列中有数据被丢弃时，下一条数据以完整快照发送。
+//
+// 推送的媒体信息不带 thumbnail_url；缩略图哈希与上一次推送成功时相同时省略 thumbnail，
+// 服务器沿用之前收到的缩略图。
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::{Duration, SystemTime};
+use rand::Rng;
+use reqwest::StatusCode;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tokio::time::Instant;
+use crate::{AppState, SettingsSection};
+use crate::config::{self, PushCompression, PushTarget, RemoteSettings, DEFAULT_PUSH_TARGET_ID};
+use crate::push_delta::DeltaEncoder;
+use crate::push_queue::PushQueue;
+use crate::system_info::{build_system_info, SystemInfo};
+
+/// 首次重试的等待时间
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(5);
+/// 重试等待时间上限
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(600);
+
+/// 推送内容的格式版本，没有 version 字段的旧版本视为 1
+pub const PUSH_FORMAT_VERSION: u32 = 2;
+
+/// 推送目标的运行状态
+#[derive(Debug, Clone, Default)]
+pub struct PushStatus {
+    pub last_push_time: Option<SystemTime>,
+    pub pending: usize,  // 离线队列中等待发送的条数
+}
+
+/// 一条采集数据：系统信息加上采集时间
+#[derive(Serialize)]
+struct PushSample {
+    timestamp: u64,  // 采集时间（Unix 秒），补发时服务器按该时间记录
+    #[serde(flatten)]
+    info: SystemInfo,
+}
+
+// 推送目标的离线队列文件，default 目标沿用单个推送目标时的文件名
+fn queue_file(target_id: &str) -> String {
+    if target_id == DEFAULT_PUSH_TARGET_ID {
+        "push_queue.jsonl".to_string()
+    } else {
+        format!("push_queue-{}.jsonl", target_id)
+    }
+}
+
+// 读取推送目标的当前设置
+fn find_target(state: &AppState, target_id: &str) -> Result<PushTarget, String> {
+    state.remote_targets
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|target| target.id == target_id)
+        .cloned()
+        .ok_or_else(|| format!("推送目标不存在: {}", target_id))
+}
+
+// 修改推送目标的启用状态并保存
+fn set_enabled(state: &AppState, target_id: &str, enabled: bool) -> Result<(), String> {
+    if let Some(target) = state.remote_targets.lock().unwrap().iter_mut().find(|target| target.id == target_id) {
+        target.remote.enabled = enabled;
+    }
+    state.save_settings(SettingsSection::RemoteTargets)
+}
+
+// 更新推送目标的运行状态
+fn update_status(state: &AppState, target_id: &str, update: impl FnOnce(&mut PushStatus)) {
+    update(state.push_status.lock().unwrap().entry(target_id.to_string()).or_default());
+}
+
+// 从采集器快照构建一条采集数据，推送目标没有单独的共享设置时使用全局设置
+fn build_sample(state: &AppState, target: &PushTarget) -> Result<Map<String, Value>, String> {
+    let share_settings = target.share
+        .clone()
+        .unwrap_or_else(|| state.share_settings.lock().unwrap().clone());
+    let process_limit = state.app_settings.lock().unwrap().process_limit;
+    let snapshot = state.collector.snapshot();
+
+    let timestamp = snapshot.taken_at
+        .unwrap_or_else(SystemTime::now)
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    match serde_json::to_value(PushSample {
+        timestamp,
+        info: build_system_info(&snapshot, &share_settings, process_limit),
+    }) {
+        Ok(Value::Object(mut fields)) => {
+            // thumbnail_url 是本地 HTTP API 的相对地址，对服务器没有意义
+            for_each_media(&mut fields, |_, media| {
+                media.remove("thumbnail_url");
+            });
+            Ok(fields)
+        }
+        Ok(_) => Err("系统信息不是 JSON 对象".to_string()),
+        Err(e) => Err(format!("序列化系统信息失败: {}", e)),
+    }
+}
+
+// 对采集数据中的每个媒体会话调用 f，第一个参数区分会话：主会话为 media，其余按来源应用
+fn for_each_media(sample: &mut Map<String, Value>, mut f: impl FnMut(String, &mut Map<String, Value>)) {
+    for (key, value) in sample.iter_mut() {
+        match (key.as_str(), value) {
+            ("media", Value::Object(media)) => f("media".to_string(), media),
+            ("media_sessions", Value::Array(sessions)) => {
+                for session in sessions {
+                    if let Value::Object(session) = session {
+                        let source = session.get("source_app_id").and_then(Value::as_str).unwrap_or_default().to_string();
+                        f(format!("session:{}", source), session);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 记录服务器已经收到的缩略图，缩略图哈希与上一次推送成功时相同的会话不再重复发送 Base64 数据
+#[derive(Default)]
+struct ThumbnailTracker {
+    pushed: HashMap<String, String>,  // 会话 -> 最近一次推送成功的缩略图哈希
+    queued: HashMap<String, String>,  // 会话 -> 队列中尚未确认的缩略图哈希
+}
+
+impl ThumbnailTracker {
+    // 去掉服务器已有的缩略图，记录仍然发送的缩略图
+    fn omit_pushed(&mut self, sample: &mut Map<String, Value>) {
+        for_each_media(sample, |slot, media| {
+            let Some(hash) = media.get("thumbnail_hash").and_then(Value::as_str).map(str::to_string) else {
+                return;
+            };
+            // 队列中还有该会话的其他缩略图时照常发送，避免服务器沿用错误的缩略图
+            let pushed = self.pushed.get(&slot) == Some(&hash)
+                && self.queued.get(&slot).is_none_or(|queued| queued == &hash);
+            if pushed {
+                media.remove("thumbnail");
+            } else if media.get("thumbnail").is_some_and(|thumbnail| !thumbnail.is_null()) {
+                self.queued.insert(slot, hash);
+            }
+        });
+    }
+
+    // 队列中的数据全部发送成功
+    fn confirm(&mut self) {
+        self.pushed.extend(self.queued.drain());
+    }
+
+    // 有数据被丢弃，无法确定哪些缩略图到达了服务器
+    fn discard_queued(&mut self) {
+        self.queued.clear();
+    }
+
+    // 服务器要求重新同步，可能已丢失之前的数据
+    fn reset(&mut self) {
+        self.pushed.clear();
+        self.queued.clear();
+    }
+}
+
+// 把采集数据序列化为队列中的一行
+fn sample_line(sample: Map<String, Value>) -> Result<String, String> {
+    serde_json::to_string(&Value::Object(sample))
+        .map_err(|e| format!("序列化系统信息失败: {}", e))
+}
+
+// 解析队列中的采集数据
+fn parse_sample(sample: &str) -> Result<Map<String, Value>, String> {
+    match serde_json::from_str(sample) {
+        Ok(Value::Object(fields)) => Ok(fields),
+        Ok(_) => Err("采集数据不是 JSON 对象".to_string()),
+        Err(e) => Err(format!("解析采集数据失败: {}", e)),
+    }
+}
+
+// 单条推送：{"version": 2, "type": "single", "timestamp": ..., 系统信息字段...}
+fn single_body(sample: &str) -> Result<String, String> {
+    let mut body = Map::new();
+    body.insert("version".to_string(), PUSH_FORMAT_VERSION.into());
+    body.insert("type".to_string(), "single".into());
+    body.extend(parse_sample(sample)?);
+
+    serde_json::to_string(&body).map_err(|e| format!("序列化推送内容失败: {}", e))
+}
+
+// 批量推送：{"version": 2, "type": "batch", "samples": [采集数据...]}
+fn batch_body(samples: &[&str]) -> Result<String, String> {
+    let samples = samples
+        .iter()
+        .map(|sample| parse_sample(sample).map(Value::Object))
+        .collect::<Result<Vec<Value>, String>>()?;
+
+    serde_json::to_string(&serde_json::json!({
+        "version": PUSH_FORMAT_VERSION,
+        "type": "batch",
+        "samples": samples,
+    }))
+    .map_err(|e| format!("序列化推送内容失败: {}", e))
+}
+
+// 第 failures 次连续失败后的等待时间：指数增长，取上限后在 [delay/2, delay] 之间随机
+fn retry_delay(failures: u32) -> Duration {
+    let exponent = failures.saturating_sub(1).min(16);
+    let delay = RETRY_BASE_DELAY
+        .saturating_mul(1 << exponent)
+        .min(RETRY_MAX_DELAY);
+    delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+}
+
+// 服务器认为数据本身有问题，重试也不会成功，丢弃该条数据以免阻塞队列
+fn is_rejected(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::BAD_REQUEST | StatusCode::PAYLOAD_TOO_LARGE | StatusCode::UNPROCESSABLE_ENTITY
+    )
+}
+
+// 按压缩方式编码请求体，返回编码后的数据和 Content-Encoding
+fn encode_body(body: &str, compression: PushCompression) -> Result<(Vec<u8>, Option<&'static str>), String> {
+    match compression {
+        PushCompression::None => Ok((body.as_bytes().to_vec(), None)),
+        PushCompression::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(body.as_bytes())
+                .and_then(|_| encoder.finish())
+                .map(|data| (data, Some("gzip")))
+                .map_err(|e| format!("gzip 压缩失败: {}", e))
+        }
+        // 级别 0 表示使用 zstd 的默认级别
+        PushCompression::Zstd => zstd::encode_all(body.as_bytes(), 0)
+            .map(|data| (data, Some("zstd")))
+            .map_err(|e| format!("zstd 压缩失败: {}", e)),
+    }
+}
+
+/// 服务器对一次推送的响应
+struct PushResponse {
+    status: StatusCode,
+    resync: bool,  // 服务器要求下一条数据发送完整快照
+}
+
+// 响应中的 resync 标记，可以在顶层或 successResponse 的 data 中
+fn wants_resync(body: &Value) -> bool {
+    [&body["resync"], &body["data"]["resync"]]
+        .iter()
+        .any(|value| value.as_bool() == Some(true))
+}
+
+// 发送一次请求，返回服务器的响应
+async fn send_encoded(
+    client: &reqwest::Client,
+    remote_settings: &RemoteSettings,
+    body: &str,
+    compression: PushCompression,
+) -> Result<PushResponse, String> {
+    let (data, encoding) = encode_body(body, compression)?;
+
+    let mut request = client
+        .post(&remote_settings.url)
+        .header("Content-Type", "application/json");
+    
+    // Add token header if provided
+    if !remote_settings.token.is_empty() {
+        request = request.header("X-Device-Token", &remote_settings.token);
+    }
+
+    if let Some(encoding) = encoding {
+        request = request.header("Content-Encoding", encoding);
+    }
+    
+    let resp = request
+        .body(data)
+        .send()
+        .await
+        .map_err(|e| format!("推送失败: {}", e))?;
+
+    let status = resp.status();
+    // 旧版服务器的响应不一定是 JSON，解析失败时视为不需要重新同步
+    let resync = status.is_success()
+        && resp.json::<Value>().await.map(|body| wants_resync(&body)).unwrap_or(false);
+
+    Ok(PushResponse { status, resync })
+}
+
+// 发送一次请求；服务器不支持压缩（415）时用未压缩的 JSON 重发，并把 compression 改为 None
+async fn send(
+    client: &reqwest::Client,
+    remote_settings: &RemoteSettings,
+    body: &str,
+    compression: &mut PushCompression,
+) -> Result<PushResponse, String> {
+    let response = send_encoded(client, remote_settings, body, *compression).await?;
+    if response.status != StatusCode::UNSUPPORTED_MEDIA_TYPE || *compression == PushCompression::None {
+        return Ok(response);
+    }
+
+    eprintln!("服务器不支持 {:?} 压缩，改为发送未压缩的数据", compression);
+    *compression = PushCompression::None;
+    send_encoded(client, remote_settings, body, PushCompression::None).await
+}
+
+// 向远程服务器推送一次数据
+async fn push_to_remote(client: &reqwest::Client, remote_settings: &RemoteSettings, body: String) -> Result<(), String> {
+    let mut compression = remote_settings.compression;
+    let status = send(client, remote_settings, &body, &mut compression).await?.status;
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(format!("推送失败，服务器返回状态码: {}", status))
+    }
+}
+
+// 按顺序发送队列中的数据，返回发送成功的条数；遇到失败时停止，剩余数据留在队列中。
+// 服务器要求重新同步时把 resync 置为 true，丢弃了数据（无法序列化或被服务器拒绝）时把 dropped 置为 true，
+// 中途失败也会保留这两个标记
+async fn flush_queue(
+    client: &reqwest::Client,
+    remote_settings: &RemoteSettings,
+    compression: &mut PushCompression,
+    queue: &mut PushQueue,
+    resync: &mut bool,
+    dropped: &mut bool,
+) -> Result<usize, String> {
+    let mut sent = 0;
+    while !queue.is_empty() {
+        // 单条模式每次发送一条，队列非空时 peek_batch 至少返回一条
+        let (batch, body) = if remote_settings.batch_enabled {
+            let batch = queue.peek_batch(remote_settings.batch_max_samples, remote_settings.batch_max_kb * 1024);
+            let body = batch_body(&batch);
+            (batch, body)
+        } else {
+            let batch = queue.peek_batch(1, 0);
+            let body = single_body(batch[0]);
+            (batch, body)
+        };
+        let count = batch.len();
+
+        let body = match body {
+            Ok(body) => body,
+            Err(e) => {
+                // 队列文件中损坏的数据无法发送，丢弃以免阻塞后面的数据
+                eprintln!("{}，已丢弃 {} 条数据", e, count);
+                queue.pop_front(count);
+                *dropped = true;
+                continue;
+            }
+        };
+
+        let response = send(client, remote_settings, &body, compression).await?;
+        let status = response.status;
+        if status.is_success() {
+            sent += count;
+            *resync |= response.resync;
+        } else if is_rejected(status) {
+            eprintln!("服务器拒绝推送数据（状态码 {}），已丢弃 {} 条数据", status, count);
+            *dropped = true;
+        } else {
+            return Err(format!("推送失败，服务器返回状态码: {}", status));
+        }
+        queue.pop_front(count);
+    }
+    Ok(sent)
+}
+
+/// 替换推送目标列表并保存，被删除的目标的推送任务会停止，未发送的数据也会删除，
+/// 以后新建同 id 的目标不会发送旧目标的数据
+pub fn set_remote_targets(state: &AppState, targets: Vec<PushTarget>) -> Result<(), String> {
+    config::validate_push_targets(&targets)?;
+
+    state.remote_push_handles.lock().unwrap().retain(|id, handle| {
+        let keep = targets.iter().any(|target| &target.id == id);
+        if !keep {
+            handle.abort();
+        }
+        keep
+    });
+    state.push_status.lock().unwrap().retain(|id, _| targets.iter().any(|target| &target.id == id));
+    let removed: Vec<PushTarget> = state.remote_targets
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|old| !targets.iter().any(|target| target.id == old.id))
+        .cloned()
+        .collect();
+    *state.remote_targets.lock().unwrap() = targets;
+
+    state.save_settings(SettingsSection::RemoteTargets)?;
+
+    for target in removed {
+        if let Err(e) = remove_queue(&target.id) {
+            eprintln!("[{}] {}", target.name, e);
+        }
+    }
+    Ok(())
+}
+
+/// 删除推送目标的离线队列文件
+pub fn remove_queue(target_id: &str) -> Result<(), String> {
+    PushQueue::remove(&queue_file(target_id))
+}
+
+/// 启动所有已启用的推送目标
+pub async fn start_enabled_targets(state: &AppState) {
+    let targets = state.remote_targets.lock().unwrap().clone();
+    for target in targets.iter().filter(|target| target.remote.enabled && !target.remote.url.is_empty()) {
+        match spawn_remote_push(state, &target.id) {
+            Ok(()) => println!("远程推送服务已启动: {} ({})", target.name, target.remote.url),
+            Err(e) => eprintln!("启动远程推送失败 {}: {}", target.name, e),
+        }
+    }
+}
+
+/// 启动推送目标的定时推送任务，并在配置中开启该目标
+pub async fn start_remote_push(state: AppState, target_id: &str) -> Result<(), String> {
+    spawn_remote_push(&state, target_id)?;
+
+    // 保存设置到文件
+    set_enabled(&state, target_id, true)
+}
+
+/// 只启动推送任务，不修改配置中的 enabled
+pub fn spawn_remote_push(state: &AppState, target_id: &str) -> Result<(), String> {
+    let target = find_target(state, target_id)?;
+    
+    if target.remote.url.is_empty() {
+        return Err("远程URL未配置".to_string());
+    }
+
+    // Stop existing task if any
+    if let Some(handle) = state.remote_push_handles.lock().unwrap().remove(target_id) {
+        handle.abort();
+    }
+
+    let state_clone = state.clone();
+    let target_id = target.id.clone();
+    let handle = tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut queue = PushQueue::load(&queue_file(&target_id), target.remote.queue_max_entries);
+        if !queue.is_empty() {
+            println!("[{}] 推送队列中有 {} 条未发送的数据", target.name, queue.len());
+        }
+
+        // 等待采集器完成首次采集，避免推送空数据
+        state_clone.collector.ready().await;
+
+        let mut next_sample = Instant::now();
+        let mut next_upload = Instant::now();
+        let mut retry_at = Instant::now();
+        let mut failures: u32 = 0;
+        let mut configured_compression = target.remote.compression;
+        let mut compression = configured_compression;
+        let mut encoder = DeltaEncoder::new();
+        let mut thumbnails = ThumbnailTracker::default();
+
+        // 目标被删除或停用时结束任务
+        while let Ok(target) = find_target(&state_clone, &target_id) {
+            let remote_settings = &target.remote;
+            
+            // Check if still enabled
+            if !remote_settings.enabled {
+                break;
+            }
+
+            // 到达推送间隔时加入新数据，退避期间也照常采样
+            let now = Instant::now();
+            if now >= next_sample {
+                let sample = build_sample(&state_clone, &target).and_then(|mut sample| {
+                    thumbnails.omit_pushed(&mut sample);
+                    if remote_settings.delta_enabled {
+                        let full_interval = Duration::from_secs(remote_settings.delta_full_seconds);
+                        sample_line(encoder.encode(sample, full_interval))
+                    } else {
+                        // 重新开启增量推送时从完整快照开始
+                        encoder.request_full();
+                        sample_line(sample)
+                    }
+                });
+                match sample {
+                    Ok(body) => {
+                        // 队列已满丢弃了旧数据，后面的增量缺少基准
+                        if queue.push(body) {
+                            thumbnails.discard_queued();
+                            if remote_settings.delta_enabled {
+                                encoder.request_full();
+                            }
+                        }
+                    }
+                    Err(e) => eprintln!("[{}] Failed to push to remote: {}", target.name, e),
+                }
+                next_sample = now + Duration::from_secs(remote_settings.interval_seconds);
+            }
+
+            // 批量模式下攒够条数或大小、或到达上传间隔时才上传；失败后按退避时间重试
+            let upload_due = !remote_settings.batch_enabled
+                || failures > 0
+                || now >= next_upload
+                || queue.len() >= remote_settings.batch_max_samples
+                || queue.total_bytes() >= remote_settings.batch_max_kb * 1024;
+
+            if upload_due && now >= retry_at && !queue.is_empty() {
+                // 设置中的压缩方式改变后重新尝试压缩
+                if remote_settings.compression != configured_compression {
+                    configured_compression = remote_settings.compression;
+                    compression = configured_compression;
+                }
+
+                let mut resync = false;
+                let mut dropped = false;
+                let result = flush_queue(&client, remote_settings, &mut compression, &mut queue, &mut resync, &mut dropped).await;
+
+                if resync {
+                    thumbnails.reset();
+                } else if dropped {
+                    thumbnails.discard_queued();
+                } else if result.is_ok() {
+                    thumbnails.confirm();
+                }
+
+                if remote_settings.delta_enabled {
+                    if resync {
+                        println!("[{}] 服务器要求重新同步，下一条数据发送完整快照", target.name);
+                        encoder.request_full();
+                    } else if dropped {
+                        println!("[{}] 有数据被丢弃，下一条数据发送完整快照", target.name);
+                        encoder.request_full();
+                    }
+                }
+
+                match result {
+                    Ok(sent) => {
+                        if failures > 0 {
+                            println!("[{}] 远程服务器已恢复，补发了 {} 条数据", target.name, sent);
+                        } else {
+                            println!("[{}] Remote push successful", target.name);
+                        }
+                        failures = 0;
+                        next_upload = Instant::now() + Duration::from_secs(remote_settings.batch_upload_seconds);
+                        // Update last push time
+                        update_status(&state_clone, &target_id, |status| status.last_push_time = Some(SystemTime::now()));
+                    }
+                    Err(e) => {
+                        failures += 1;
+                        let delay = retry_delay(failures);
+                        eprintln!(
+                            "[{}] Failed to push to remote: {}（{} 条数据待发送，{:.0} 秒后重试）",
+                            target.name,
+                            e,
+                            queue.len(),
+                            delay.as_secs_f64()
+                        );
+                        retry_at = Instant::now() + delay;
+                    }
+                }
+            }
+
+            if let Err(e) = queue.save() {
+                eprintln!("[{}] {}", target.name, e);
+            }
+            update_status(&state_clone, &target_id, |status| status.pending = queue.len());
+
+            // 等到下一次采样、上传或重试
+            let wake_at = if queue.is_empty() {
+                next_sample
+            } else if failures > 0 {
+                next_sample.min(retry_at)
+            } else if remote_settings.batch_enabled {
+                next_sample.min(next_upload)
+            } else {
+                next_sample
+            };
+            tokio::time::sleep_until(wake_at).await;
+        }
+    });
+
+    state.remote_push_handles.lock().unwrap().insert(target.id.clone(), handle);
+    Ok(())
+}
+
+/// 停止推送目标的定时推送任务
+pub fn stop_remote_push(state: &AppState, target_id: &str) -> Result<(), String> {
+    if let Some(handle) = state.remote_push_handles.lock().unwrap().remove(target_id) {
+        handle.abort();
+    }
+    
+    // 保存设置到文件
+    set_enabled(state, target_id, false)
+}
+
+/// 向推送目标立即推送一次（用于测试连接）
+pub async fn push_once(state: &AppState, target_id: &str) -> Result<(), String> {
+    let target = find_target(state, target_id)?;
+    
+    if target.remote.url.is_empty() {
+        return Err("远程URL未配置".to_string());
+    }
+
+    let body = single_body(&sample_line(build_sample(state, &target)?)?)?;
+    let client = reqwest::Client::new();
+    push_to_remote(&client, &target.remote, body).await?;
+
+    // Update last push time
+    update_status(state, target_id, |status| status.last_push_time = Some(SystemTime::now()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_jittered(failures: u32, expected: Duration) {
+        for _ in 0..100 {
+            let delay = retry_delay(failures);
+            assert!(delay >= expected / 2 && delay <= expected, "{} 次失败: {:?}", failures, delay);
+        }
+    }
+
+    #[test]
+    fn retry_delay_grows_exponentially_with_jitter() {
+        assert_jittered(0, RETRY_BASE_DELAY);
+        assert_jittered(1, RETRY_BASE_DELAY);
+        assert_jittered(2, RETRY_BASE_DELAY * 2);
+        assert_jittered(5, RETRY_BASE_DELAY * 16);
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        assert_jittered(8, RETRY_MAX_DELAY);
+        assert_jittered(u32::MAX, RETRY_MAX_DELAY);
+    }
+
+    fn media_sample(hash: &str) -> Map<String, Value> {
+        let media = serde_json::json!({ "title": "歌曲", "thumbnail": format!("base64-{}", hash), "thumbnail_hash": hash, "source_app_id": "player" });
+        serde_json::json!({ "media": media, "media_sessions": [media] }).as_object().unwrap().clone()
+    }
+
+    fn has_thumbnails(sample: &Map<String, Value>) -> (bool, bool) {
+        (
+            sample["media"].get("thumbnail").is_some(),
+            sample["media_sessions"][0].get("thumbnail").is_some(),
+        )
+    }
+
+    #[test]
+    fn omits_thumbnail_after_successful_push() {
+        let mut thumbnails = ThumbnailTracker::default();
+
+        // 推送成功前照常发送
+        let mut sample = media_sample("a");
+        thumbnails.omit_pushed(&mut sample);
+        assert_eq!(has_thumbnails(&sample), (true, true));
+        let mut sample = media_sample("a");
+        thumbnails.omit_pushed(&mut sample);
+        assert_eq!(has_thumbnails(&sample), (true, true));
+
+        thumbnails.confirm();
+        let mut sample = media_sample("a");
+        thumbnails.omit_pushed(&mut sample);
+        assert_eq!(has_thumbnails(&sample), (false, false));
+        assert_eq!(sample["media"]["thumbnail_hash"], "a");
+
+        // 缩略图变化后重新发送
+        let mut sample = media_sample("b");
+        thumbnails.omit_pushed(&mut sample);
+        assert_eq!(has_thumbnails(&sample), (true, true));
+        // 队列中还有 b 时切回 a 也要发送
+        let mut sample = media_sample("a");
+        thumbnails.omit_pushed(&mut sample);
+        assert_eq!(has_thumbnails(&sample), (true, true));
+    }
+
+    #[test]
+    fn resends_thumbnail_after_drop_or_resync() {
+        let mut thumbnails = ThumbnailTracker::default();
+        thumbnails.omit_pushed(&mut media_sample("a"));
+        thumbnails.discard_queued();
+        thumbnails.confirm();
+        let mut sample = media_sample("a");
+        thumbnails.omit_pushed(&mut sample);
+        assert_eq!(has_thumbnails(&sample), (true, true));
+
+        thumbnails.confirm();
+        thumbnails.reset();
+        let mut sample = media_sample("a");
+        thumbnails.omit_pushed(&mut sample);
+        assert_eq!(has_thumbnails(&sample), (true, true));
+    }
+
+    #[test]
+    fn ignores_sessions_without_inline_thumbnail() {
+        let mut thumbnails = ThumbnailTracker::default();
+        let mut sample = serde_json::json!({ "media": { "thumbnail": null, "thumbnail_hash": "a" } }).as_object().unwrap().clone();
+        thumbnails.omit_pushed(&mut sample);
+        thumbnails.confirm();
+        assert!(thumbnails.pushed.is_empty());
+        assert!(sample["media"]["thumbnail"].is_null());
+    }
+}
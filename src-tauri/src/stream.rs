@@ -0,0 +1,318 @@
+// 实时推送：Server-Sent Events (/api/stream) 与 WebSocket (/api/ws)
+//
+// 订阅后台采集器，每次采集完成后发送一条消息。第一条消息总是完整快照；
+// `?mode=changes` 时之后只发送发生变化的顶层字段，没有变化的采集周期不发送。
+// 每次发送前重新检查令牌，令牌被吊销、过期或开启了认证要求后连接会被关闭；
+// HTTP 服务器停止时连接也会关闭。
+use std::sync::Arc;
+use axum::{
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    Extension,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Response,
+    },
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tokio_stream::{wrappers::WatchStream, Stream, StreamExt};
+use crate::AppState;
+use crate::auth::ApiAccess;
+use crate::http_server::{visible_system_info, ServerShutdown};
+use crate::push_delta::{strip_fields, VOLATILE_MEDIA_FIELDS};
+
+/// WebSocket 策略违规关闭码（RFC 6455）
+const CLOSE_POLICY_VIOLATION: u16 = 1008;
+/// WebSocket 服务端离开关闭码（RFC 6455）
+const CLOSE_GOING_AWAY: u16 = 1001;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamMode {
+    #[default]
+    Full,     // 每次都发送完整快照
+    Changes,  // 首次发送完整快照，之后只发送变化的字段
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamParams {
+    #[serde(default)]
+    pub mode: StreamMode,
+}
+
+// 记录上一次发送的内容，用于计算变化的字段
+struct Differ {
+    mode: StreamMode,
+    last: Option<Map<String, Value>>,
+}
+
+impl Differ {
+    fn new(mode: StreamMode) -> Self {
+        Self { mode, last: None }
+    }
+
+    // 返回 (消息类型, 数据)，没有变化时返回 None
+    fn next(&mut self, current: Map<String, Value>) -> Option<(&'static str, Value)> {
+        let previous = match (self.mode, self.last.take()) {
+            (StreamMode::Changes, Some(previous)) => previous,
+            _ => {
+                self.last = Some(current.clone());
+                return Some(("snapshot", Value::Object(current)));
+            }
+        };
+
+        let changes: Map<String, Value> = current
+            .iter()
+            .filter(|(key, value)| !previous.get(*key).is_some_and(|previous| unchanged(key, previous, value)))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+
+        self.last = Some(current);
+        if changes.is_empty() {
+            None
+        } else {
+            Some(("changes", Value::Object(changes)))
+        }
+    }
+}
+
+// 媒体的播放位置每次采样都会变化，与远程推送的增量模式相同，只有它变化时不算变化，
+// 客户端按 position_updated_at 和 playback_rate 自行推算
+fn unchanged(key: &str, previous: &Value, current: &Value) -> bool {
+    match key {
+        "media" | "media_sessions" => {
+            strip_fields(previous, &VOLATILE_MEDIA_FIELDS) == strip_fields(current, &VOLATILE_MEDIA_FIELDS)
+        }
+        _ => previous == current,
+    }
+}
+
+// 从快照构建当前连接可见的系统信息，序列化为 JSON 对象
+fn next_message(
+    state: &AppState,
+    access: &ApiAccess,
+    differ: &mut Differ,
+    snapshot: &crate::collector::Snapshot,
+) -> Option<(&'static str, Value)> {
+    // 首次采集完成前的空快照不发送
+    snapshot.taken_at?;
+
+    match serde_json::to_value(visible_system_info(state, access, snapshot)) {
+        Ok(Value::Object(current)) => differ.next(current),
+        Ok(_) => None,
+        Err(e) => {
+            eprintln!("序列化系统信息失败: {}", e);
+            None
+        }
+    }
+}
+
+/// GET /api/stream
+pub async fn sse_handler(
+    State(state): State<Arc<AppState>>,
+    access: ApiAccess,
+    Extension(shutdown): Extension<ServerShutdown>,
+    Query(params): Query<StreamParams>,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let mut differ = Differ::new(params.mode);
+
+    // WatchStream 会先产出当前快照，之后每次更新产出一次
+    let stream = WatchStream::new(state.collector.subscribe())
+        .map_while(move |snapshot| {
+            if !access.is_still_valid(&state) {
+                return None;
+            }
+            Some(next_message(&state, &access, &mut differ, &snapshot))
+        })
+        .filter_map(|message| message.map(|(kind, data)| Event::default().event(kind).json_data(data)));
+    // 服务器停止时结束，连接随之关闭
+    let stream = futures_util::StreamExt::take_until(stream, shutdown.wait());
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// GET /api/ws
+pub async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    access: ApiAccess,
+    Extension(shutdown): Extension<ServerShutdown>,
+    Query(params): Query<StreamParams>,
+) -> Response {
+    ws.on_upgrade(move |socket| run_socket(socket, state, access, shutdown, params.mode))
+}
+
+async fn run_socket(mut socket: WebSocket, state: Arc<AppState>, access: ApiAccess, shutdown: ServerShutdown, mode: StreamMode) {
+    let mut receiver = state.collector.subscribe();
+    let mut differ = Differ::new(mode);
+    let stopped = shutdown.wait();
+    tokio::pin!(stopped);
+
+    // 连接建立后立即发送当前快照
+    receiver.mark_changed();
+
+    loop {
+        tokio::select! {
+            _ = &mut stopped => {
+                let _ = socket.send(Message::Close(Some(CloseFrame {
+                    code: CLOSE_GOING_AWAY,
+                    reason: "服务器已停止".into(),
+                }))).await;
+                break;
+            }
+            changed = receiver.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+
+                if !access.is_still_valid(&state) {
+                    let _ = socket.send(Message::Close(Some(CloseFrame {
+                        code: CLOSE_POLICY_VIOLATION,
+                        reason: "访问令牌已失效".into(),
+                    }))).await;
+                    break;
+                }
+
+                let snapshot = receiver.borrow_and_update().clone();
+                if let Some((kind, data)) = next_message(&state, &access, &mut differ, &snapshot) {
+                    let text = serde_json::json!({ "type": kind, "data": data }).to_string();
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            message = socket.recv() => {
+                // 客户端发来的消息只用于检测断开，Ping 由底层自动回复
+                match message {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+    use crate::config::Config;
+    use crate::http_server::{start_http_server, stop_http_server};
+
+    fn sample(fields: Value) -> Map<String, Value> {
+        match fields {
+            Value::Object(fields) => fields,
+            _ => unreachable!(),
+        }
+    }
+
+    fn media(title: &str, position_ms: u64) -> Value {
+        serde_json::json!({
+            "title": title,
+            "position": position_ms / 1000,
+            "position_ms": position_ms,
+            "position_updated_at": 1_700_000_000_000u64 + position_ms,
+            "playback_status": "Playing",
+        })
+    }
+
+    #[test]
+    fn changes_mode_ignores_media_position() {
+        let mut differ = Differ::new(StreamMode::Changes);
+        let (kind, _) = differ.next(sample(serde_json::json!({
+            "uptime": 1, "media": media("A", 0), "media_sessions": [media("A", 0)],
+        }))).unwrap();
+        assert_eq!(kind, "snapshot");
+
+        // 只有播放位置变化时不发送
+        assert!(differ.next(sample(serde_json::json!({
+            "uptime": 1, "media": media("A", 2_000), "media_sessions": [media("A", 2_000)],
+        }))).is_none());
+
+        // 其他字段变化时只发送变化的分项
+        let (kind, data) = differ.next(sample(serde_json::json!({
+            "uptime": 2, "media": media("A", 4_000), "media_sessions": [media("A", 4_000)],
+        }))).unwrap();
+        assert_eq!(kind, "changes");
+        assert_eq!(data, serde_json::json!({ "uptime": 2 }));
+
+        // 曲目变化时发送完整的媒体分项，包括新的播放位置
+        let (_, data) = differ.next(sample(serde_json::json!({
+            "uptime": 2, "media": media("B", 0), "media_sessions": [media("B", 0)],
+        }))).unwrap();
+        assert_eq!(data, serde_json::json!({ "media": media("B", 0), "media_sessions": [media("B", 0)] }));
+    }
+
+    #[test]
+    fn full_mode_always_sends_snapshot() {
+        let mut differ = Differ::new(StreamMode::Full);
+        for _ in 0..2 {
+            let (kind, data) = differ.next(sample(serde_json::json!({ "uptime": 1 }))).unwrap();
+            assert_eq!(kind, "snapshot");
+            assert_eq!(data, serde_json::json!({ "uptime": 1 }));
+        }
+    }
+
+    // 在随机空闲端口上启动只监听本机的服务器
+    async fn start_server() -> (AppState, u16) {
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let mut config = Config::default();
+        config.http.port = port;
+        config.http.bind_addresses = vec!["127.0.0.1".to_string()];
+        config.http.require_auth = false;
+
+        let state = AppState::new(&config);
+        start_http_server(state.clone()).await.unwrap();
+        (state, port)
+    }
+
+    // 发送请求并读取到响应头结束
+    async fn open(port: u16, request: &str) -> (TcpStream, String) {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut head = Vec::new();
+        let mut byte = [0u8; 1];
+        while !head.ends_with(b"\r\n\r\n") {
+            assert_eq!(stream.read(&mut byte).await.unwrap(), 1, "连接提前关闭");
+            head.push(byte[0]);
+        }
+        (stream, String::from_utf8_lossy(&head).into_owned())
+    }
+
+    // 读取到连接关闭为止
+    async fn read_until_closed(stream: &mut TcpStream) {
+        let mut buffer = [0u8; 4096];
+        let closed = tokio::time::timeout(Duration::from_secs(5), async {
+            while stream.read(&mut buffer).await.is_ok_and(|read| read > 0) {}
+        })
+        .await;
+        assert!(closed.is_ok(), "停止服务器后连接仍未关闭");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn stopping_server_closes_open_streams() {
+        let (state, port) = start_server().await;
+
+        let (mut sse, head) = open(port, &format!(
+            "GET /api/stream HTTP/1.1\r\nHost: 127.0.0.1:{}\r\n\r\n", port
+        )).await;
+        assert!(head.starts_with("HTTP/1.1 200"), "{}", head);
+
+        let (mut ws, head) = open(port, &format!(
+            "GET /api/ws HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+             Sec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n", port
+        )).await;
+        assert!(head.starts_with("HTTP/1.1 101"), "{}", head);
+
+        stop_http_server(&state);
+        read_until_closed(&mut sse).await;
+        read_until_closed(&mut ws).await;
+        assert!(TcpStream::connect(("127.0.0.1", port)).await.is_err());
+    }
+}
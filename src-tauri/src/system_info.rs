@@ -0,0 +1,201 @@
+// 对外输出的系统信息结构（HTTP API 与远程推送共用）
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use crate::battery::BatteryInfo;
+use crate::collector::Snapshot;
+use crate::config::ShareSettings;
+use crate::media_monitor::MediaInfo;
+
+#[derive(Serialize)]
+pub struct SystemInfo {
+    pub computer_name: Option<String>,
+    pub uptime: Option<u64>,
+    pub cpu_usage: Option<Vec<f32>>,
+    pub memory_usage: Option<MemoryInfo>,
+    pub processes: Option<Vec<ProcessInfo>>,
+    pub disks: Option<Vec<DiskInfo>>,
+    pub network: Option<Vec<NetworkInfo>>,
+    pub battery: Option<BatteryInfo>,
+    pub media: Option<MediaInfo>,                 // 主媒体会话
+    pub media_sessions: Option<Vec<MediaInfo>>,   // 所有媒体会话
+}
+
+#[derive(Serialize)]
+pub struct MemoryInfo {
+    pub total: u64,
+    pub used: u64,
+    pub percent: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ProcessInfo {
+    pub memory: u64,
+    pub is_focused: bool,
+    pub window_title: String,
+    pub executable_name: String,
+    pub pid: u32,
+    pub cpu_usage: f32,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct DiskInfo {
+    pub name: String,
+    pub mount_point: String,
+    pub total_space: u64,
+    pub available_space: u64,
+}
+
+#[derive(Serialize)]
+pub struct NetworkInfo {
+    pub name: String,
+    pub received: u64,
+    pub transmitted: u64,
+}
+
+/// 进程列表排序方式
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessSort {
+    #[default]
+    Cpu,     // CPU 占用率降序
+    Memory,  // 内存占用降序
+    Name,    // 可执行文件名升序
+}
+
+/// 进程列表的筛选条件
+#[derive(Debug, Clone, Default)]
+pub struct ProcessQuery {
+    pub limit: u32,
+    pub sort: ProcessSort,
+    pub name: Option<String>,  // 按可执行文件名或窗口标题筛选（不区分大小写）
+}
+
+// 按筛选条件选出进程，聚焦进程不在前 N 个中时也会被加入
+fn select_processes(processes: &[ProcessInfo], query: &ProcessQuery) -> Vec<ProcessInfo> {
+    let name = query.name.as_ref().map(|name| name.to_lowercase());
+    let mut matched: Vec<&ProcessInfo> = processes
+        .iter()
+        .filter(|p| match &name {
+            Some(name) => p.executable_name.to_lowercase().contains(name)
+                || p.window_title.to_lowercase().contains(name),
+            None => true,
+        })
+        .collect();
+
+    // Snapshot processes are already sorted by CPU usage descending
+    match query.sort {
+        ProcessSort::Cpu => {}
+        ProcessSort::Memory => matched.sort_by_key(|p| std::cmp::Reverse(p.memory)),
+        ProcessSort::Name => matched.sort_by_key(|p| p.executable_name.to_lowercase()),
+    }
+
+    let mut result: Vec<ProcessInfo> = matched.iter().take(query.limit as usize).map(|p| (*p).clone()).collect();
+
+    // Add focused process if it's not already in the list
+    if let Some(focused) = matched.iter().find(|p| p.is_focused) {
+        if !result.iter().any(|p| p.pid == focused.pid) {
+            result.insert(0, (*focused).clone());
+        }
+    }
+
+    result
+}
+
+// 根据共享设置从快照构建对外输出的系统信息
+pub fn build_system_info(snapshot: &Snapshot, share_settings: &ShareSettings, process_limit: u32) -> SystemInfo {
+    let query = ProcessQuery {
+        limit: process_limit,
+        ..Default::default()
+    };
+    build_system_info_with(snapshot, share_settings, &query)
+}
+
+/// 与 build_system_info 相同，但使用自定义的进程筛选条件
+pub fn build_system_info_with(snapshot: &Snapshot, share_settings: &ShareSettings, process_query: &ProcessQuery) -> SystemInfo {
+    let computer_name = if share_settings.share_computer_name {
+        snapshot.computer_name.clone()
+    } else {
+        None
+    };
+
+    let uptime = if share_settings.share_uptime {
+        Some(snapshot.uptime)
+    } else {
+        None
+    };
+
+    let cpu_usage = if share_settings.share_cpu_usage {
+        Some(snapshot.cpu_usage.clone())
+    } else {
+        None
+    };
+
+    let memory_usage = if share_settings.share_memory_usage {
+        let total = snapshot.memory_total;
+        let used = snapshot.memory_used;
+        Some(MemoryInfo {
+            total,
+            used,
+            percent: if total > 0 { (used as f64 / total as f64) * 100.0 } else { 0.0 },
+        })
+    } else {
+        None
+    };
+
+    let processes = if share_settings.share_processes {
+        let mut processes = select_processes(&snapshot.processes, process_query);
+        if share_settings.focused_process_only {
+            processes.retain(|p| p.is_focused);
+        }
+        Some(processes)
+    } else {
+        None
+    };
+
+    let disks = if share_settings.share_disks {
+        Some(snapshot.disks.clone())
+    } else {
+        None
+    };
+
+    let network = if share_settings.share_network {
+        Some(
+            snapshot.networks
+                .iter()
+                .map(|network| NetworkInfo {
+                    name: network.name.clone(),
+                    received: network.received,
+                    transmitted: network.transmitted,
+                })
+                .collect(),
+        )
+    } else {
+        None
+    };
+
+    let battery = if share_settings.share_battery {
+        snapshot.battery.clone()
+    } else {
+        None
+    };
+
+    // 快照可能是几秒前采集的，播放位置推算到读取时刻
+    let media_sessions = if share_settings.share_media {
+        Some(snapshot.media_sessions_at(SystemTime::now()))
+    } else {
+        None
+    };
+
+    SystemInfo {
+        computer_name,
+        uptime,
+        cpu_usage,
+        memory_usage,
+        processes,
+        disks,
+        network,
+        battery,
+        media: media_sessions.as_ref().and_then(|sessions| sessions.first().cloned()),
+        media_sessions,
+    }
+}
@@ -0,0 +1,87 @@
+// 前台窗口与窗口标题检测
+#[cfg(windows)]
+mod platform {
+    use std::collections::HashMap;
+    use windows::Win32::Foundation::{HWND, LPARAM, BOOL};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetForegroundWindow, GetWindowThreadProcessId, EnumWindows, GetWindowTextW, IsWindowVisible,
+    };
+
+    pub fn get_focused_pid() -> Option<u32> {
+        unsafe {
+            let hwnd = GetForegroundWindow();
+            if hwnd.0 == 0 {
+                return None;
+            }
+
+            let mut pid: u32 = 0;
+            GetWindowThreadProcessId(hwnd, Some(&mut pid));
+            
+            if pid == 0 {
+                None
+            } else {
+                Some(pid)
+            }
+        }
+    }
+
+    pub fn get_window_titles() -> HashMap<u32, String> {
+        unsafe {
+            let mut titles: HashMap<u32, String> = HashMap::new();
+            
+            extern "system" fn enum_callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
+                unsafe {
+                    let titles = &mut *(lparam.0 as *mut HashMap<u32, String>);
+                    
+                    // Only process visible windows
+                    if !IsWindowVisible(hwnd).as_bool() {
+                        return BOOL(1);
+                    }
+
+                    let mut pid: u32 = 0;
+                    GetWindowThreadProcessId(hwnd, Some(&mut pid));
+
+                    let mut text: [u16; 512] = [0; 512];
+                    let len = GetWindowTextW(hwnd, &mut text);
+                    
+                    if len > 0 {
+                        let title = String::from_utf16_lossy(&text[..len as usize]);
+                        if !title.is_empty() && pid != 0 {
+                            // Store the first non-empty title for each PID
+                            titles.entry(pid).or_insert(title);
+                        }
+                    }
+
+                    BOOL(1) // Continue enumeration
+                }
+            }
+
+            let _ = EnumWindows(
+                Some(enum_callback),
+                LPARAM(&mut titles as *mut _ as isize),
+            );
+
+            titles
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    pub use crate::linux_windows::{get_focused_pid, get_window_titles};
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+mod platform {
+    use std::collections::HashMap;
+
+    pub fn get_focused_pid() -> Option<u32> {
+        None
+    }
+
+    pub fn get_window_titles() -> HashMap<u32, String> {
+        HashMap::new()
+    }
+}
+
+pub use platform::{get_focused_pid, get_window_titles};
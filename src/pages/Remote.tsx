@@ -9,46 +9,121 @@ import { Badge } from "@/components/ui/badge";
 import { Separator } from "@/components/ui/separator";
 import { toast } from "sonner";
 
-interface RemoteSettings {
+interface ShareSettings {
+  share_computer_name: boolean;
+  share_uptime: boolean;
+  share_cpu_usage: boolean;
+  share_memory_usage: boolean;
+  share_processes: boolean;
+  share_disks: boolean;
+  share_network: boolean;
+  share_battery: boolean;
+  share_media: boolean;
+  focused_process_only: boolean;
+}
+
+interface PushTarget {
+  id: string;
+  name: string;
+  share: ShareSettings | null;
   enabled: boolean;
   url: string;
   token: string;
   interval_seconds: number;
+  queue_max_entries: number;
+  batch_enabled: boolean;
+  batch_upload_seconds: number;
+  batch_max_samples: number;
+  batch_max_kb: number;
+  compression: "none" | "gzip" | "zstd";
+  delta_enabled: boolean;
+  delta_full_seconds: number;
 }
 
+const SHARE_OPTIONS: { key: keyof ShareSettings; label: string }[] = [
+  { key: "share_computer_name", label: "电脑名称" },
+  { key: "share_uptime", label: "运行时间" },
+  { key: "share_cpu_usage", label: "CPU使用率" },
+  { key: "share_memory_usage", label: "内存使用率" },
+  { key: "share_processes", label: "应用进程信息" },
+  { key: "focused_process_only", label: "仅当前聚焦的应用" },
+  { key: "share_disks", label: "硬盘信息" },
+  { key: "share_network", label: "网络信息" },
+  { key: "share_battery", label: "电池信息" },
+  { key: "share_media", label: "媒体播放" },
+];
+
+const newTarget = (index: number): PushTarget => ({
+  id: `target-${Date.now()}`,
+  name: `推送目标 ${index + 1}`,
+  share: null,
+  enabled: false,
+  url: "",
+  token: "",
+  interval_seconds: 60,
+  queue_max_entries: 1440,
+  batch_enabled: false,
+  batch_upload_seconds: 60,
+  batch_max_samples: 20,
+  batch_max_kb: 512,
+  compression: "none",
+  delta_enabled: false,
+  delta_full_seconds: 600,
+});
+
 export function Remote() {
-  const [settings, setSettings] = useState<RemoteSettings>({
-    enabled: false,
-    url: "",
-    token: "",
-    interval_seconds: 60,
-  });
+  const [targets, setTargets] = useState<PushTarget[]>([]);
+  const [selectedId, setSelectedId] = useState<string>("");
   const [lastPushTime, setLastPushTime] = useState<string>("");
+  const [pendingCount, setPendingCount] = useState<number>(0);
+
+  const settings = targets.find((target) => target.id === selectedId);
+  const setSettings = (updated: PushTarget) =>
+    setTargets(targets.map((target) => (target.id === updated.id ? updated : target)));
 
   useEffect(() => {
     loadSettings();
   }, []);
 
+  useEffect(() => {
+    loadStatus(selectedId);
+  }, [selectedId]);
+
   const loadSettings = async () => {
     try {
-      const remoteSettings = await invoke<RemoteSettings>("get_remote_settings");
-      setSettings(remoteSettings);
-      
-      // 获取上次推送时间
-      try {
-        const lastTime = await invoke<string>("get_last_push_time");
-        setLastPushTime(lastTime);
-      } catch (e) {
-        console.log("No last push time available");
+      const remoteTargets = await invoke<PushTarget[]>("get_remote_targets");
+      setTargets(remoteTargets);
+      if (!remoteTargets.some((target) => target.id === selectedId)) {
+        setSelectedId(remoteTargets[0]?.id ?? "");
       }
     } catch (error) {
-      console.error("Failed to load remote settings:", error);
+      console.error("Failed to load remote targets:", error);
+    }
+  };
+
+  const loadStatus = async (id: string) => {
+    if (!id) {
+      return;
+    }
+
+    // 获取上次推送时间
+    try {
+      const lastTime = await invoke<string>("get_last_push_time", { id });
+      setLastPushTime(lastTime);
+    } catch (e) {
+      setLastPushTime("");
+    }
+
+    try {
+      setPendingCount(await invoke<number>("get_pending_push_count", { id }));
+    } catch (error) {
+      console.error("Failed to load push status:", error);
     }
   };
 
   const handleSaveSettings = async () => {
     try {
-      await invoke("set_remote_settings", { settings });
+      await invoke("set_remote_targets", { targets });
       toast.success("远程推送设置已保存");
     } catch (error: any) {
       toast.error(`保存设置失败: ${error}`);
@@ -56,166 +131,426 @@ export function Remote() {
     }
   };
 
+  const handleAddTarget = () => {
+    const target = newTarget(targets.length);
+    setTargets([...targets, target]);
+    setSelectedId(target.id);
+  };
+
+  const handleDeleteTarget = async () => {
+    if (!settings) {
+      return;
+    }
+
+    const remaining = targets.filter((target) => target.id !== settings.id);
+    try {
+      await invoke("set_remote_targets", { targets: remaining });
+      setTargets(remaining);
+      setSelectedId(remaining[0]?.id ?? "");
+      toast.success(`已删除推送目标 ${settings.name}`);
+    } catch (error: any) {
+      toast.error(`删除推送目标失败: ${error}`);
+      console.error(error);
+    }
+  };
+
   const toggleRemotePush = async (enabled: boolean) => {
-    const newSettings = { ...settings, enabled };
-    setSettings(newSettings);
+    if (!settings) {
+      return;
+    }
+
+    const previous = targets;
+    const newTargets = targets.map((target) => (target.id === settings.id ? { ...target, enabled } : target));
+    setTargets(newTargets);
     
     try {
-      await invoke("set_remote_settings", { settings: newSettings });
+      await invoke("set_remote_targets", { targets: newTargets });
       
       if (enabled) {
-        await invoke("start_remote_push");
+        await invoke("start_remote_push", { id: settings.id });
         toast.success("远程推送已启动");
       } else {
-        await invoke("stop_remote_push");
+        await invoke("stop_remote_push", { id: settings.id });
         toast.success("远程推送已停止");
       }
     } catch (error: any) {
       toast.error(`切换远程推送状态失败: ${error}`);
       console.error(error);
       // 回滚状态
-      setSettings(settings);
+      setTargets(previous);
     }
   };
 
   const handleTestPush = async () => {
-    if (!settings.url) {
+    if (!settings?.url) {
       toast.error("请先设置推送URL");
       return;
     }
 
     try {
       toast.info("正在测试推送...");
-      await invoke("test_remote_push");
+      // 测试推送使用已保存的设置，先保存新建或修改过的目标
+      await invoke("set_remote_targets", { targets });
+      await invoke("test_remote_push", { id: settings.id });
       toast.success("测试推送成功！");
-      loadSettings(); // 刷新上次推送时间
+      loadStatus(settings.id); // 刷新上次推送时间
     } catch (error: any) {
       toast.error(`测试推送失败: ${error}`);
       console.error(error);
     }
   };
 
+  const setShareOverride = (share: ShareSettings | null) => {
+    if (settings) {
+      setSettings({ ...settings, share });
+    }
+  };
+
+  const enableShareOverride = async (enabled: boolean) => {
+    if (!enabled) {
+      setShareOverride(null);
+      return;
+    }
+
+    // 从全局共享设置开始修改
+    try {
+      setShareOverride(await invoke<ShareSettings>("get_share_settings"));
+    } catch (error) {
+      console.error("Failed to load share settings:", error);
+    }
+  };
+
   return (
     <div className="space-y-6">
       <Card>
         <CardHeader>
-          <CardTitle>远程推送设置</CardTitle>
-          <CardDescription>配置自动推送系统监控数据到远程服务器</CardDescription>
+          <CardTitle>推送目标</CardTitle>
+          <CardDescription>每个目标独立推送，可以使用不同的服务器、间隔和共享内容</CardDescription>
         </CardHeader>
-        <CardContent className="space-y-4">
-          <div className="space-y-2">
-            <Label htmlFor="url">推送URL</Label>
-            <Input
-              id="url"
-              type="url"
-              value={settings.url}
-              onChange={(e) => setSettings({ ...settings, url: e.target.value })}
-              placeholder="https://example.com/api/receive.php"
-            />
-            <p className="text-xs text-muted-foreground">
-              系统监控数据将通过POST请求发送到此URL
-            </p>
+        <CardContent>
+          <div className="flex flex-wrap gap-2">
+            {targets.map((target) => (
+              <Button
+                key={target.id}
+                variant={target.id === selectedId ? "default" : "outline"}
+                onClick={() => setSelectedId(target.id)}
+              >
+                {target.name || target.id}
+                {target.enabled && <Badge variant="secondary" className="ml-2">运行中</Badge>}
+              </Button>
+            ))}
+            <Button variant="outline" onClick={handleAddTarget}>
+              添加推送目标
+            </Button>
           </div>
+        </CardContent>
+      </Card>
 
-          <Separator />
-
-          <div className="space-y-2">
-            <Label htmlFor="token">设备Token</Label>
-            <Input
-              id="token"
-              type="password"
-              value={settings.token}
-              onChange={(e) => setSettings({ ...settings, token: e.target.value })}
-              placeholder="从服务器管理后台获取的设备Token"
-            />
-            <p className="text-xs text-muted-foreground">
-              用于验证设备身份的唯一Token，请在服务器管理后台创建设备后获取
-            </p>
-          </div>
+      {settings && (
+        <>
+          <Card>
+            <CardHeader>
+              <CardTitle>远程推送设置</CardTitle>
+              <CardDescription>配置自动推送系统监控数据到远程服务器</CardDescription>
+            </CardHeader>
+            <CardContent className="space-y-4">
+              <div className="space-y-2">
+                <Label htmlFor="name">名称</Label>
+                <Input
+                  id="name"
+                  value={settings.name}
+                  onChange={(e) => setSettings({ ...settings, name: e.target.value })}
+                  placeholder="例如：内部服务器、公开状态页"
+                />
+              </div>
 
-          <Separator />
-
-          <div className="space-y-2">
-            <Label htmlFor="interval">推送间隔（秒）</Label>
-            <Input
-              id="interval"
-              type="number"
-              min="10"
-              max="3600"
-              value={settings.interval_seconds}
-              onChange={(e) => setSettings({ ...settings, interval_seconds: parseInt(e.target.value) || 60 })}
-            />
-            <p className="text-xs text-muted-foreground">
-              每隔多少秒自动推送一次数据（最小10秒，最大3600秒）
-            </p>
-          </div>
+              <Separator />
 
-          <Separator />
+              <div className="space-y-2">
+                <Label htmlFor="url">推送URL</Label>
+                <Input
+                  id="url"
+                  type="url"
+                  value={settings.url}
+                  onChange={(e) => setSettings({ ...settings, url: e.target.value })}
+                  placeholder="https://example.com/api/receive.php"
+                />
+                <p className="text-xs text-muted-foreground">
+                  系统监控数据将通过POST请求发送到此URL
+                </p>
+              </div>
 
-          <div className="flex items-center justify-between">
-            <Button onClick={handleSaveSettings}>
-              保存设置
-            </Button>
-            <Button onClick={handleTestPush} variant="outline">
-              测试推送
-            </Button>
-          </div>
-        </CardContent>
-      </Card>
+              <Separator />
 
-      <Card>
-        <CardHeader>
-          <CardTitle>推送状态</CardTitle>
-          <CardDescription>管理自动推送功能</CardDescription>
-        </CardHeader>
-        <CardContent className="space-y-4">
-          <div className="flex items-center justify-between">
-            <div className="space-y-0.5">
-              <Label>自动推送</Label>
-              <p className="text-sm text-muted-foreground">
-                {settings.enabled ? "推送功能已启用" : "推送功能已禁用"}
-              </p>
-            </div>
-            <div className="flex items-center space-x-2">
-              <Badge variant={settings.enabled ? "default" : "secondary"}>
-                {settings.enabled ? "运行中" : "已停止"}
-              </Badge>
-              <Switch
-                checked={settings.enabled}
-                onCheckedChange={toggleRemotePush}
-              />
-            </div>
-          </div>
+              <div className="space-y-2">
+                <Label htmlFor="token">设备Token</Label>
+                <Input
+                  id="token"
+                  type="password"
+                  value={settings.token}
+                  onChange={(e) => setSettings({ ...settings, token: e.target.value })}
+                  placeholder="从服务器管理后台获取的设备Token"
+                />
+                <p className="text-xs text-muted-foreground">
+                  用于验证设备身份的唯一Token，请在服务器管理后台创建设备后获取
+                </p>
+              </div>
 
-          <Separator />
+              <Separator />
 
-          <div className="space-y-2">
-            <Label>上次推送时间</Label>
-            <p className="text-sm text-muted-foreground">
-              {lastPushTime || "暂无推送记录"}
-            </p>
-          </div>
+              <div className="space-y-2">
+                <Label htmlFor="interval">{settings.batch_enabled ? "采样间隔（秒）" : "推送间隔（秒）"}</Label>
+                <Input
+                  id="interval"
+                  type="number"
+                  min={settings.batch_enabled ? "1" : "10"}
+                  max="3600"
+                  value={settings.interval_seconds}
+                  onChange={(e) => setSettings({ ...settings, interval_seconds: parseInt(e.target.value) || 60 })}
+                />
+                <p className="text-xs text-muted-foreground">
+                  {settings.batch_enabled
+                    ? "每隔多少秒记录一次数据，按下方的批量设置上传（最小1秒，最大3600秒）"
+                    : "每隔多少秒自动推送一次数据（最小10秒，最大3600秒）"}
+                </p>
+              </div>
+
+              <Separator />
+
+              <div className="space-y-2">
+                <Label htmlFor="compression">请求压缩</Label>
+                <select
+                  id="compression"
+                  className="h-10 w-full rounded-md border border-input bg-background px-3 text-sm"
+                  value={settings.compression}
+                  onChange={(e) => setSettings({ ...settings, compression: e.target.value as PushTarget["compression"] })}
+                >
+                  <option value="none">不压缩</option>
+                  <option value="gzip">gzip</option>
+                  <option value="zstd">zstd</option>
+                </select>
+                <p className="text-xs text-muted-foreground">
+                  压缩推送数据以节省流量，适合按流量计费的网络；服务器不支持时自动改为发送未压缩的数据
+                </p>
+              </div>
+
+              <Separator />
 
-          <Separator />
+              <div className="flex items-center justify-between">
+                <div className="space-y-0.5">
+                  <Label>批量推送</Label>
+                  <p className="text-sm text-muted-foreground">
+                    按采样间隔记录数据，攒够一批后一次上传，减少请求次数
+                  </p>
+                </div>
+                <Switch
+                  checked={settings.batch_enabled}
+                  onCheckedChange={(checked) => setSettings({ ...settings, batch_enabled: checked })}
+                />
+              </div>
 
-          <div className="space-y-2">
-            <Label>推送数据格式</Label>
-            <div className="rounded-lg bg-muted p-4">
-              <pre className="text-xs overflow-x-auto">
-{`{
-  "system_info": { ... },
+              {settings.batch_enabled && (
+                <div className="grid grid-cols-3 gap-4">
+                  <div className="space-y-2">
+                    <Label htmlFor="batch-upload-seconds">上传间隔（秒）</Label>
+                    <Input
+                      id="batch-upload-seconds"
+                      type="number"
+                      min="1"
+                      value={settings.batch_upload_seconds}
+                      onChange={(e) => setSettings({ ...settings, batch_upload_seconds: parseInt(e.target.value) || 60 })}
+                    />
+                  </div>
+                  <div className="space-y-2">
+                    <Label htmlFor="batch-max-samples">每批最多条数</Label>
+                    <Input
+                      id="batch-max-samples"
+                      type="number"
+                      min="1"
+                      value={settings.batch_max_samples}
+                      onChange={(e) => setSettings({ ...settings, batch_max_samples: parseInt(e.target.value) || 20 })}
+                    />
+                  </div>
+                  <div className="space-y-2">
+                    <Label htmlFor="batch-max-kb">每批最大大小（KB）</Label>
+                    <Input
+                      id="batch-max-kb"
+                      type="number"
+                      min="1"
+                      value={settings.batch_max_kb}
+                      onChange={(e) => setSettings({ ...settings, batch_max_kb: parseInt(e.target.value) || 512 })}
+                    />
+                  </div>
+                </div>
+              )}
+
+              <Separator />
+
+              <div className="flex items-center justify-between">
+                <div className="space-y-0.5">
+                  <Label>增量推送</Label>
+                  <p className="text-sm text-muted-foreground">
+                    只发送发生变化的部分，定期发送一次完整数据；需要服务器支持增量格式
+                  </p>
+                </div>
+                <Switch
+                  checked={settings.delta_enabled}
+                  onCheckedChange={(checked) => setSettings({ ...settings, delta_enabled: checked })}
+                />
+              </div>
+
+              {settings.delta_enabled && (
+                <div className="space-y-2">
+                  <Label htmlFor="delta-full-seconds">完整数据间隔（秒）</Label>
+                  <Input
+                    id="delta-full-seconds"
+                    type="number"
+                    min="10"
+                    value={settings.delta_full_seconds}
+                    onChange={(e) => setSettings({ ...settings, delta_full_seconds: parseInt(e.target.value) || 600 })}
+                  />
+                  <p className="text-xs text-muted-foreground">
+                    每隔多少秒发送一次完整数据；服务器发现数据缺失时也会要求立即发送完整数据
+                  </p>
+                </div>
+              )}
+
+              <Separator />
+
+              <div className="flex items-center justify-between">
+                <div className="space-y-0.5">
+                  <Label>单独设置共享内容</Label>
+                  <p className="text-sm text-muted-foreground">
+                    {settings.share ? "该目标只推送下方选择的数据" : "使用设置页中的数据共享设置"}
+                  </p>
+                </div>
+                <Switch
+                  checked={settings.share !== null}
+                  onCheckedChange={enableShareOverride}
+                />
+              </div>
+
+              {settings.share && (
+                <div className="grid grid-cols-2 gap-4">
+                  {SHARE_OPTIONS.map(({ key, label }) => (
+                    <div key={key} className="flex items-center justify-between">
+                      <Label>{label}</Label>
+                      <Switch
+                        checked={settings.share![key]}
+                        disabled={key === "focused_process_only" && !settings.share!.share_processes}
+                        onCheckedChange={(checked) => setShareOverride({ ...settings.share!, [key]: checked })}
+                      />
+                    </div>
+                  ))}
+                </div>
+              )}
+
+              <Separator />
+
+              <div className="space-y-2">
+                <Label htmlFor="queue-max-entries">离线队列容量（条）</Label>
+                <Input
+                  id="queue-max-entries"
+                  type="number"
+                  min="1"
+                  value={settings.queue_max_entries}
+                  onChange={(e) => setSettings({ ...settings, queue_max_entries: parseInt(e.target.value) || 1440 })}
+                />
+                <p className="text-xs text-muted-foreground">
+                  推送失败的数据会保存在本地，服务器恢复后按原时间补发；超过容量时丢弃最早的数据
+                </p>
+              </div>
+
+              <Separator />
+
+              <div className="flex items-center justify-between">
+                <Button onClick={handleSaveSettings}>
+                  保存设置
+                </Button>
+                <div className="flex items-center space-x-2">
+                  <Button onClick={handleTestPush} variant="outline">
+                    测试推送
+                  </Button>
+                  <Button onClick={handleDeleteTarget} variant="destructive">
+                    删除目标
+                  </Button>
+                </div>
+              </div>
+            </CardContent>
+          </Card>
+
+          <Card>
+            <CardHeader>
+              <CardTitle>推送状态</CardTitle>
+              <CardDescription>管理自动推送功能</CardDescription>
+            </CardHeader>
+            <CardContent className="space-y-4">
+              <div className="flex items-center justify-between">
+                <div className="space-y-0.5">
+                  <Label>自动推送</Label>
+                  <p className="text-sm text-muted-foreground">
+                    {settings.enabled ? "推送功能已启用" : "推送功能已禁用"}
+                  </p>
+                </div>
+                <div className="flex items-center space-x-2">
+                  <Badge variant={settings.enabled ? "default" : "secondary"}>
+                    {settings.enabled ? "运行中" : "已停止"}
+                  </Badge>
+                  <Switch
+                    checked={settings.enabled}
+                    onCheckedChange={toggleRemotePush}
+                  />
+                </div>
+              </div>
+
+              <Separator />
+
+              <div className="space-y-2">
+                <Label>上次推送时间</Label>
+                <p className="text-sm text-muted-foreground">
+                  {lastPushTime || "暂无推送记录"}
+                </p>
+              </div>
+
+              <Separator />
+
+              <div className="space-y-2">
+                <Label>待补发数据</Label>
+                <p className="text-sm text-muted-foreground">
+                  {pendingCount > 0 ? `${pendingCount} 条数据等待重新发送` : "无"}
+                </p>
+              </div>
+
+              <Separator />
+
+              <div className="space-y-2">
+                <Label>推送数据格式</Label>
+                <div className="rounded-lg bg-muted p-4">
+                  <pre className="text-xs overflow-x-auto">
+{settings.batch_enabled ? `{
+  "version": 2,
+  "type": "batch",
+  "samples": [
+    { "timestamp": ..., "processes": [ ... ], ... }
+  ]
+}` : `{
+  "version": 2,
+  "type": "single",
+  "timestamp": ...,
   "processes": [ ... ],
   "disks": [ ... ],
-  "network": { ... }
+  "network": [ ... ]
 }`}
-              </pre>
-            </div>
-            <p className="text-xs text-muted-foreground">
-              数据格式与HTTP API返回的JSON格式相同
-            </p>
-          </div>
-        </CardContent>
-      </Card>
+                  </pre>
+                </div>
+                <p className="text-xs text-muted-foreground">
+                  数据格式与HTTP API返回的JSON格式相同
+                </p>
+              </div>
+            </CardContent>
+          </Card>
+        </>
+      )}
     </div>
   );
 }
@@ -2,6 +2,7 @@ import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/tauri";
 import { Card, CardContent, CardDescription, CardHeader, CardTitle } from "@/components/ui/card";
 import { Input } from "@/components/ui/input";
+import { Textarea } from "@/components/ui/textarea";
 import { Label } from "@/components/ui/label";
 import { Button } from "@/components/ui/button";
 import { Switch } from "@/components/ui/switch";
@@ -11,6 +12,8 @@ import { toast } from "sonner";
 
 interface HttpSettings {
   port: number;
+  bind_addresses: string[];
+  allowed_origins: string[];
   is_running: boolean;
 }
 
@@ -22,6 +25,9 @@ interface ShareSettings {
   share_processes: boolean;
   share_disks: boolean;
   share_network: boolean;
+  share_battery: boolean;
+  share_media: boolean;
+  focused_process_only: boolean;
 }
 
 interface AppSettings {
@@ -29,17 +35,35 @@ interface AppSettings {
   auto_launch: boolean;
   silent_launch: boolean;
   process_limit: number;
+  sample_interval_ms: number;
 }
 
+type MediaType = "Music" | "Video" | "Podcast" | "Stream";
+
+const MEDIA_TYPES: MediaType[] = ["Music", "Video", "Podcast", "Stream"];
+
+interface MediaTypeRule {
+  app: string;
+  media_type: MediaType;
+}
+
+type ThumbnailFormat = "jpeg" | "png" | "webp";
+
 interface MediaSettings {
   enabled: boolean;
   send_thumbnail: boolean;
+  inline_thumbnail: boolean;
   compress_thumbnail: boolean;
   thumbnail_max_size_kb: number;
+  thumbnail_max_dimension: number;
+  thumbnail_format: ThumbnailFormat;
+  media_type_rules: MediaTypeRule[];
 }
 
 export function Settings() {
   const [httpPort, setHttpPort] = useState<number>(21536);
+  const [bindAddresses, setBindAddresses] = useState<string>("127.0.0.1");
+  const [allowedOrigins, setAllowedOrigins] = useState<string>("");
   const [httpRunning, setHttpRunning] = useState<boolean>(false);
   const [shareSettings, setShareSettings] = useState<ShareSettings>({
     share_computer_name: true,
@@ -49,19 +73,28 @@ export function Settings() {
     share_processes: true,
     share_disks: true,
     share_network: true,
+    share_battery: true,
+    share_media: true,
+    focused_process_only: false,
   });
   const [appSettings, setAppSettings] = useState<AppSettings>({
     auto_start_http: true,
     auto_launch: false,
     silent_launch: false,
     process_limit: 20,
+    sample_interval_ms: 2000,
   });
   const [mediaSettings, setMediaSettings] = useState<MediaSettings>({
     enabled: true,
     send_thumbnail: false,
+    inline_thumbnail: true,
     compress_thumbnail: true,
     thumbnail_max_size_kb: 16,
+    thumbnail_max_dimension: 0,
+    thumbnail_format: "jpeg",
+    media_type_rules: [],
   });
+  const [mediaTypeRules, setMediaTypeRules] = useState<string>("");
   const [previewUrl, setPreviewUrl] = useState<string>("");
 
   useEffect(() => {
@@ -76,17 +109,20 @@ export function Settings() {
       const media = await invoke<MediaSettings>("get_media_settings");
       
       setHttpPort(httpSettings.port);
+      setBindAddresses(httpSettings.bind_addresses.join(", "));
+      setAllowedOrigins(httpSettings.allowed_origins.join(", "));
       setHttpRunning(httpSettings.is_running);
       setShareSettings(share);
       setAppSettings(app);
       setMediaSettings(media);
+      setMediaTypeRules(media.media_type_rules.map((rule) => `${rule.app}=${rule.media_type}`).join("\n"));
       setPreviewUrl(`http://localhost:${httpSettings.port}/api/system`);
     } catch (error) {
       console.error("Failed to load settings:", error);
     }
   };
 
-  const handleMediaSettingChange = async (key: keyof MediaSettings, value: boolean | number) => {
+  const handleMediaSettingChange = async (key: keyof MediaSettings, value: boolean | number | string) => {
     const newSettings = { ...mediaSettings, [key]: value };
     setMediaSettings(newSettings);
     
@@ -99,6 +135,31 @@ export function Settings() {
     }
   };
 
+  const handleMediaTypeRulesChange = async () => {
+    const rules: MediaTypeRule[] = [];
+    for (const line of mediaTypeRules.split("\n")) {
+      if (line.trim().length === 0) continue;
+      const index = line.lastIndexOf("=");
+      const app = line.slice(0, index).trim();
+      const mediaType = line.slice(index + 1).trim() as MediaType;
+      if (index < 0 || app.length === 0 || !MEDIA_TYPES.includes(mediaType)) {
+        toast.error(`无效的规则: ${line}`);
+        return;
+      }
+      rules.push({ app, media_type: mediaType });
+    }
+
+    const newSettings = { ...mediaSettings, media_type_rules: rules };
+    try {
+      await invoke("set_media_settings", { settings: newSettings });
+      setMediaSettings(newSettings);
+      toast.success("媒体类型规则已保存");
+    } catch (error) {
+      toast.error("保存媒体类型规则失败");
+      console.error(error);
+    }
+  };
+
   const handlePortChange = async () => {
     try {
       await invoke("set_http_port", { port: httpPort });
@@ -110,6 +171,36 @@ export function Settings() {
     }
   };
 
+  const handleBindAddressesChange = async () => {
+    const addresses = bindAddresses
+      .split(",")
+      .map((addr) => addr.trim())
+      .filter((addr) => addr.length > 0);
+
+    try {
+      await invoke("set_http_bind_addresses", { addresses });
+      toast.success("监听地址已保存，重启HTTP服务器后生效");
+    } catch (error) {
+      toast.error(`设置监听地址失败: ${error}`);
+      console.error(error);
+    }
+  };
+
+  const handleAllowedOriginsChange = async () => {
+    const origins = allowedOrigins
+      .split(",")
+      .map((origin) => origin.trim())
+      .filter((origin) => origin.length > 0);
+
+    try {
+      await invoke("set_http_allowed_origins", { origins });
+      toast.success("跨域来源已保存，重启HTTP服务器后生效");
+    } catch (error) {
+      toast.error(`设置跨域来源失败: ${error}`);
+      console.error(error);
+    }
+  };
+
   const toggleHttpServer = async () => {
     try {
       if (httpRunning) {
@@ -167,6 +258,42 @@ export function Settings() {
               保存端口
             </Button>
           </div>
+
+          <div className="flex items-center space-x-4">
+            <div className="flex-1 space-y-2">
+              <Label htmlFor="bind-addresses">监听地址</Label>
+              <p className="text-sm text-muted-foreground">
+                多个地址用逗号分隔。127.0.0.1 仅本机可访问，0.0.0.0 或 :: 对局域网开放（:: 为 IPv6 双栈）
+              </p>
+              <Input
+                id="bind-addresses"
+                value={bindAddresses}
+                onChange={(e) => setBindAddresses(e.target.value)}
+                placeholder="127.0.0.1"
+              />
+            </div>
+            <Button onClick={handleBindAddressesChange} className="mt-14">
+              保存地址
+            </Button>
+          </div>
+
+          <div className="flex items-center space-x-4">
+            <div className="flex-1 space-y-2">
+              <Label htmlFor="allowed-origins">允许跨域访问的网页</Label>
+              <p className="text-sm text-muted-foreground">
+                多个来源用逗号分隔，例如 https://example.com。留空时其他网页无法读取本机数据
+              </p>
+              <Input
+                id="allowed-origins"
+                value={allowedOrigins}
+                onChange={(e) => setAllowedOrigins(e.target.value)}
+                placeholder="https://example.com"
+              />
+            </div>
+            <Button onClick={handleAllowedOriginsChange} className="mt-14">
+              保存来源
+            </Button>
+          </div>
           
           <Separator />
           
@@ -270,6 +397,18 @@ export function Settings() {
             />
           </div>
 
+          <div className="flex items-center justify-between pl-4">
+            <div className="space-y-0.5">
+              <Label>仅当前聚焦的应用</Label>
+              <p className="text-sm text-muted-foreground">只共享前台窗口对应的进程，隐藏其他应用</p>
+            </div>
+            <Switch
+              checked={shareSettings.focused_process_only}
+              disabled={!shareSettings.share_processes}
+              onCheckedChange={(checked) => handleShareSettingChange("focused_process_only", checked)}
+            />
+          </div>
+
           <Separator />
 
           <div className="flex items-center justify-between">
@@ -295,6 +434,32 @@ export function Settings() {
               onCheckedChange={(checked) => handleShareSettingChange("share_network", checked)}
             />
           </div>
+
+          <Separator />
+
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>电池信息</Label>
+              <p className="text-sm text-muted-foreground">电量和充电状态</p>
+            </div>
+            <Switch
+              checked={shareSettings.share_battery}
+              onCheckedChange={(checked) => handleShareSettingChange("share_battery", checked)}
+            />
+          </div>
+
+          <Separator />
+
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>媒体播放</Label>
+              <p className="text-sm text-muted-foreground">正在播放的音乐和视频</p>
+            </div>
+            <Switch
+              checked={shareSettings.share_media}
+              onCheckedChange={(checked) => handleShareSettingChange("share_media", checked)}
+            />
+          </div>
         </CardContent>
       </Card>
 
@@ -410,13 +575,50 @@ export function Settings() {
               </Button>
             </div>
           </div>
+
+          <Separator />
+
+          <div className="space-y-2">
+            <Label htmlFor="sample-interval">后台采集间隔（毫秒）</Label>
+            <p className="text-sm text-muted-foreground">
+              系统信息由后台任务按此间隔刷新，HTTP API、仪表盘和远程推送都读取最新一次的采集结果
+            </p>
+            <div className="flex items-center space-x-4">
+              <Input
+                id="sample-interval"
+                type="number"
+                min="500"
+                max="60000"
+                step="500"
+                value={appSettings.sample_interval_ms}
+                onChange={(e) => {
+                  const value = parseInt(e.target.value) || 2000;
+                  setAppSettings({ ...appSettings, sample_interval_ms: value });
+                }}
+                className="w-32"
+              />
+              <Button
+                onClick={async () => {
+                  try {
+                    await invoke("set_app_settings", { settings: appSettings });
+                    toast.success("采集间隔已更新");
+                  } catch (error) {
+                    toast.error("更新设置失败");
+                    console.error(error);
+                  }
+                }}
+              >
+                保存
+              </Button>
+            </div>
+          </div>
         </CardContent>
       </Card>
 
       <Card>
         <CardHeader>
           <CardTitle>媒体播放监控设置</CardTitle>
-          <CardDescription>配置媒体播放状态监控功能（仅Windows）</CardDescription>
+          <CardDescription>配置媒体播放状态监控功能（Windows / Linux）</CardDescription>
         </CardHeader>
         <CardContent className="space-y-4">
           <div className="flex items-center justify-between">
@@ -448,6 +650,22 @@ export function Settings() {
 
           <Separator />
 
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label>内嵌缩略图</Label>
+              <p className="text-sm text-muted-foreground">
+                在媒体信息中附带 Base64 缩略图，关闭后客户端通过 /api/media/thumbnail 获取图片
+              </p>
+            </div>
+            <Switch
+              disabled={!mediaSettings.enabled || !mediaSettings.send_thumbnail}
+              checked={mediaSettings.inline_thumbnail}
+              onCheckedChange={(checked) => handleMediaSettingChange("inline_thumbnail", checked)}
+            />
+          </div>
+
+          <Separator />
+
           <div className="flex items-center justify-between">
             <div className="space-y-0.5">
               <Label>压缩缩略图</Label>
@@ -505,6 +723,76 @@ export function Settings() {
 
           <Separator />
 
+          <div className="space-y-2">
+            <Label htmlFor="thumbnail-dimension">缩略图最大边长 (像素)</Label>
+            <p className="text-sm text-muted-foreground">
+              先缩放到该尺寸再压缩，0 表示不限制。页面只显示小图时，较小的尺寸可以在同样大小下获得更清晰的图片
+            </p>
+            <div className="flex items-center space-x-4">
+              <Input
+                id="thumbnail-dimension"
+                type="number"
+                min="0"
+                max="1024"
+                disabled={!mediaSettings.enabled || !mediaSettings.send_thumbnail || !mediaSettings.compress_thumbnail}
+                value={mediaSettings.thumbnail_max_dimension}
+                onChange={(e) => {
+                  const value = parseInt(e.target.value) || 0;
+                  setMediaSettings({ ...mediaSettings, thumbnail_max_dimension: value });
+                }}
+                className="w-32"
+              />
+              <Button
+                disabled={!mediaSettings.enabled || !mediaSettings.send_thumbnail || !mediaSettings.compress_thumbnail}
+                onClick={() => handleMediaSettingChange("thumbnail_max_dimension", mediaSettings.thumbnail_max_dimension)}
+              >
+                保存
+              </Button>
+            </div>
+          </div>
+
+          <div className="flex items-center justify-between">
+            <div className="space-y-0.5">
+              <Label htmlFor="thumbnail-format">缩略图格式</Label>
+              <p className="text-sm text-muted-foreground">
+                JPEG 会自动选择不超过大小限制的最高质量；PNG、WebP 为无损格式，只通过缩小尺寸满足大小限制
+              </p>
+            </div>
+            <select
+              id="thumbnail-format"
+              className="h-10 rounded-md border border-input bg-background px-3 text-sm"
+              disabled={!mediaSettings.enabled || !mediaSettings.send_thumbnail || !mediaSettings.compress_thumbnail}
+              value={mediaSettings.thumbnail_format}
+              onChange={(e) => handleMediaSettingChange("thumbnail_format", e.target.value)}
+            >
+              <option value="jpeg">JPEG</option>
+              <option value="png">PNG</option>
+              <option value="webp">WebP</option>
+            </select>
+          </div>
+
+          <Separator />
+
+          <div className="space-y-2">
+            <Label htmlFor="media-type-rules">媒体类型规则</Label>
+            <p className="text-sm text-muted-foreground">
+              每行一条，格式为 应用=类型，类型可选 Music、Video、Podcast、Stream。来源应用的标识或名称包含该应用名时使用对应类型，都不匹配时按有无艺术家/专辑判断
+            </p>
+            <Textarea
+              id="media-type-rules"
+              rows={4}
+              disabled={!mediaSettings.enabled}
+              placeholder={"Pocket Casts=Podcast\nTwitch=Stream"}
+              value={mediaTypeRules}
+              onChange={(e) => setMediaTypeRules(e.target.value)}
+            />
+            <Button disabled={!mediaSettings.enabled} onClick={handleMediaTypeRulesChange}>
+              保存
+            </Button>
+          </div>
+
+          <Separator />
+
           <div className="rounded-lg border border-yellow-200 bg-yellow-50 p-4 dark:border-yellow-800 dark:bg-yellow-950">
             <div className="flex">
               <div className="flex-shrink-0">
@@ -516,8 +804,8 @@ export function Settings() {
                 <h3 className="text-sm font-medium text-yellow-800 dark:text-yellow-200">注意事项</h3>
                 <div className="mt-2 text-sm text-yellow-700 dark:text-yellow-300">
                   <ul className="list-disc list-inside space-y-1">
-                    <li>此功能仅在Windows 10/11上可用</li>
-                    <li>需要媒体播放器支持Windows Media Control API</li>
+                    <li>此功能在Windows 10/11和Linux上可用</li>
+                    <li>Windows需要媒体播放器支持Windows Media Control API，Linux需要支持MPRIS</li>
                     <li>支持的播放器：Spotify、Chrome、VLC等</li>
                     <li>发送封面会增加网络流量（每次约16KB）</li>
                   </ul>